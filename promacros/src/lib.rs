@@ -9,7 +9,7 @@ extern crate rustc_plugin;
 
 use std::vec::Vec;
 use syntax::ptr::P;
-use syntax::ast::{Expr,Item};
+use syntax::ast::{Expr,ExprKind,Item,UnOp};
 use syntax::parse::token::*;
 use syntax::tokenstream::*;
 use syntax::util::small_vector::SmallVector;
@@ -83,6 +83,46 @@ fn parse_expr_pro(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
     })
 }
 
+/// Translates a signal expression made of signals, `&&`, `||`, `!` and parentheses
+/// into a `SignalExpr`.
+fn parse_signal_expr(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
+    if args.len() == 0 {
+        cx.span_err(sp, "Empty signal expression ?");
+        return DummyResult::raw_expr(sp);
+    }
+
+    // || binds less than &&, and both are left associative
+    for &(ref op, meth) in &[(Token::OrOr, "or"), (Token::AndAnd, "and")] {
+        for i in (0..args.len()).rev() {
+            if let TokenTree::Token(_, ref tok) = args[i] {
+                if tok == op {
+                    let (s1, s2tmp) = args.split_at(i);
+                    let (_, s2) = s2tmp.split_at(1);
+                    let sp1 = sp.until(args[i].span());
+                    let sp2 = args[i].span().end_point().to(sp.end_point());
+                    let e1 = parse_signal_expr(cx, sp1, s1);
+                    let e2 = parse_signal_expr(cx, sp2, s2);
+                    return cx.expr_method_call(sp, e1, cx.ident_of(meth), vec![e2]);
+                }
+            }
+        }
+    }
+
+    if let TokenTree::Token(_, Token::Not) = args[0] {
+        let e = parse_signal_expr(cx, sp, &args[1..]);
+        return cx.expr_unary(sp, UnOp::Not, e);
+    }
+
+    if args.len() == 1 {
+        if let TokenTree::Delimited(sp, Delimited { delim: DelimToken::Paren, tts: ref ts }) = args[0] {
+            return parse_signal_expr(cx, sp, &extract_ts(ts.clone().into()));
+        }
+    }
+
+    let s = parse_expr(cx, sp, args);
+    cx.expr_call_ident(sp, cx.ident_of("sig_expr"), vec![s])
+}
+
 fn split_on_binop(
     cx: &mut ExtCtxt,
     sp: Span,
@@ -261,9 +301,39 @@ fn parse_pro(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
                 let n2 = parse_pro(cx, args[2].span(), &args[2..3]);
                 return cx.expr_method_call(sp, n1, cx.ident_of("present"), vec![n2]);
             }
+            let name = id.name.as_str();
+            if name == "when" || name == "every" || name == "loop_each" || name == "trap" {
+                let s = parse_expr(cx, args[1].span(), &args[1..2]);
+                let n1 = parse_pro(cx, args[2].span(), &args[2..3]);
+                return cx.expr_method_call(sp, n1, cx.ident_of(&name), vec![s]);
+            }
+            if name == "for" || name == "while" {
+                let e = parse_expr(cx, args[1].span(), &args[1..2]);
+                let n1 = parse_pro(cx, args[2].span(), &args[2..3]);
+                let fun = if name == "for" { "pfor" } else { "pwhile" };
+                return cx.expr_call_ident(sp, cx.ident_of(fun), vec![e, n1]);
+            }
         }
     }
 
+    // keyword (signal) {P} {Q} constructs
+    if args.len() == 4 {
+        if let TokenTree::Token(_, Ident(id)) = args[0] {
+            let name = id.name.as_str();
+            if name == "abort" || name == "until" {
+                let s = parse_expr(cx, args[1].span(), &args[1..2]);
+                let n1 = parse_pro(cx, args[2].span(), &args[2..3]);
+                let n2 = parse_pro(cx, args[3].span(), &args[3..4]);
+                return cx.expr_method_call(sp, n1, cx.ident_of(&name), vec![s, n2]);
+            }
+            if name == "present" {
+                let e = parse_signal_expr(cx, args[1].span(), &args[1..2]);
+                let n1 = parse_pro(cx, args[2].span(), &args[2..3]);
+                let n2 = parse_pro(cx, args[3].span(), &args[3..4]);
+                return cx.expr_method_call(sp, n1, cx.ident_of("present_expr"), vec![e, n2]);
+            }
+        }
+    }
 
     // reverse for type inference (left associativity)
     for i in (0..args.len()).rev() {
@@ -271,6 +341,16 @@ fn parse_pro(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
             TokenTree::Token(_, ref tok) => {
                 match tok {
                     &Token::Semi => {
+                        if i > 0 {
+                            if let TokenTree::Token(_, Token::Question) = args[i - 1] {
+                                // P?; Q
+                                let sp1 = sp.until(args[i - 1].span());
+                                let sp2 = args[i].span().end_point().to(sp.end_point());
+                                let p1 = parse_pro(cx, sp1, &args[..i - 1]);
+                                let p2 = parse_pro(cx, sp2, &args[i + 1..]);
+                                return cx.expr_method_call(sp, p1, cx.ident_of("seq_try"), vec![p2]);
+                            }
+                        }
                         let (p1, p2) = split_on_binop(cx, sp, args, i);
                         return cx.expr_method_call(sp, p1, cx.ident_of("seq"), vec![p2]);
                     }
@@ -286,9 +366,90 @@ fn parse_pro(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
         }
     }
 
+    // signal s [default d gather g] in {P} construct
+    if args.len() >= 4 {
+        if let (&TokenTree::Token(_, Ident(id)), &TokenTree::Token(_, Ident(name)), &TokenTree::Token(_, Ident(kin)))
+            = (&args[0], &args[1], &args[args.len() - 2])
+        {
+            if id.name.as_str() == "signal" && kin.name.as_str() == "in" {
+                return parse_local_signal(cx, sp, name, &args[2..args.len() - 2], &args[args.len() - 1..]);
+            }
+        }
+    }
+
+    // match (selector) {P0} {P1} ... construct
+    if args.len() >= 3 {
+        if let TokenTree::Token(_, Ident(id)) = args[0] {
+            if id.name.as_str() == "match" {
+                // { let mut v = Vec::new(); v.push(P0); ...; pswitch(selector, v) }
+                let v = cx.ident_of("__switch_branches");
+                let new = cx.expr_call(sp, cx.expr_path(cx.path_global(sp, vec![
+                    cx.ident_of("std"), cx.ident_of("vec"), cx.ident_of("Vec"), cx.ident_of("new")
+                ])), vec![]);
+                let mut stmts = vec![cx.stmt_let(sp, true, v, new)];
+                for i in 2..args.len() {
+                    let n = parse_pro(cx, args[i].span(), &args[i..i + 1]);
+                    let b = cx.expr_call_ident(sp, cx.ident_of("boxed_branch"), vec![n]);
+                    let push = cx.expr_method_call(sp, cx.expr_ident(sp, v), cx.ident_of("push"), vec![b]);
+                    stmts.push(cx.stmt_semi(push));
+                }
+                let sel = parse_expr(cx, args[1].span(), &args[1..2]);
+                let call = cx.expr_call_ident(sp, cx.ident_of("pswitch"), vec![sel, cx.expr_ident(sp, v)]);
+                stmts.push(cx.stmt_expr(call));
+                return cx.expr_block(cx.block(sp, stmts));
+            }
+        }
+    }
+
     parse_expr_pro(cx, sp, args)
 }
 
+/// Expands `signal name [default d gather g] in {P}`, where `decl` is `[default d gather g]`,
+/// to `{ let name = LocalSignal::new(); local_signal(name.clone(), || new_signal, P) }`
+fn parse_local_signal(
+    cx: &mut ExtCtxt,
+    sp: Span,
+    name: syntax::ast::Ident,
+    decl: &[TokenTree],
+    body: &[TokenTree],
+) -> P<Expr> {
+    let new_signal = if decl.len() == 0 {
+        let path = cx.path(sp, vec![cx.ident_of("SignalRuntimeRef"), cx.ident_of("new_pure")]);
+        cx.expr_call(sp, cx.expr_path(path), vec![])
+    } else {
+        let gather = decl.iter().position(|t| match *t {
+            TokenTree::Token(_, Ident(id)) => id.name.as_str() == "gather",
+            _ => false,
+        });
+        let is_default = match decl[0] {
+            TokenTree::Token(_, Ident(id)) => id.name.as_str() == "default",
+            _ => false,
+        };
+        match gather {
+            Some(ind) if is_default && ind > 1 && ind + 1 < decl.len() => {
+                let d = parse_expr(cx, sp, &decl[1..ind]);
+                let g = parse_expr(cx, sp, &decl[ind + 1..]);
+                let g = cx.expr(sp, ExprKind::Box(g));
+                let path = cx.path(sp, vec![cx.ident_of("SignalRuntimeRef"), cx.ident_of("new_mc")]);
+                cx.expr_call(sp, cx.expr_path(path), vec![d, g])
+            }
+            _ => {
+                cx.span_err(sp, "expected `signal s in {P}` or `signal s default d gather g in {P}`");
+                return DummyResult::raw_expr(sp);
+            }
+        }
+    };
+
+    let path = cx.path(sp, vec![cx.ident_of("LocalSignal"), cx.ident_of("new")]);
+    let new_local = cx.expr_call(sp, cx.expr_path(path), vec![]);
+    let handle = cx.expr_method_call(sp, cx.expr_ident(sp, name), cx.ident_of("clone"), vec![]);
+    let factory = cx.lambda0(sp, new_signal);
+    let p = parse_pro(cx, body[0].span(), body);
+    let call = cx.expr_call_ident(sp, cx.ident_of("local_signal"), vec![handle, factory, p]);
+    let stmts = vec![cx.stmt_let(sp, false, name, new_local), cx.stmt_expr(call)];
+    cx.expr_block(cx.block(sp, stmts))
+}
+
 fn parse_node(cx: &mut ExtCtxt, sp: Span, args: &[TokenTree]) -> P<Expr> {
     if args.len() == 0 {
         cx.expr_ident(sp,cx.ident_of("Nothing"));
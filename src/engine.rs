@@ -6,14 +6,60 @@ use utility::*;
 use super::*;
 
 
-#[cfg(feature = "par")]
-use crossbeam;
-
-
 pub trait EndOfInstantCallback<'a>: Val<'a> {
     fn on_end_of_instant(&self, sub_runtime: &mut SubRuntime<'a>);
 }
 
+/// A main node scheduled in the runtime, with the tag of the execution that scheduled it.
+///
+/// A task inherits the tag of the task that schedules it, and a new tag is taken with
+/// `SubRuntime::new_tag` each time a preemptible body is entered. Tags are increasing,
+/// so a guard drops the tasks of a killed execution of its body by comparing their tag
+/// with the one of the last entry of the body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Task {
+    /// The id of the main node.
+    pub node: usize,
+    /// The tag of the execution the node belongs to.
+    pub tag: usize,
+}
+
+/// How the threads of a parallel runtime wait at the instant barrier,
+/// for work to steal or for the other threads to finish the instant.
+///
+/// A waiting thread first spins, then yields, then parks until it is woken.
+/// It is ignored in sequential mode.
+#[derive(Clone, Copy, Debug)]
+pub struct WaitPolicy {
+    /// The number of rounds spent spinning before yielding.
+    pub spins: usize,
+    /// The number of rounds spent yielding before parking.
+    pub yields: usize,
+    /// Whether the thread is parked after that, otherwise it keeps yielding.
+    pub park: bool,
+}
+
+impl WaitPolicy {
+    /// Always spins, the lowest latency but a waiting thread uses a whole core.
+    pub fn spin() -> Self {
+        WaitPolicy {
+            spins: usize::max_value(),
+            yields: 0,
+            park: false,
+        }
+    }
+}
+
+impl Default for WaitPolicy {
+    fn default() -> Self {
+        WaitPolicy {
+            spins: 64,
+            yields: 16,
+            park: true,
+        }
+    }
+}
+
 
 
 #[cfg(not(feature = "par"))]
@@ -26,10 +72,10 @@ mod runtime {
         /// Contains nodes to be executed on the current instants.
         /// Nodes can add other nodes' id to continue the execution in an other node
         /// on the same instant.
-        pub(crate) current: Vec<usize>,
+        pub(crate) current: Vec<Task>,
         /// Contains nodes to be executed on the next instants.
         /// Nodes can add other nodes' id and stop to pause their execution until the next instant.
-        pub(crate) next: Vec<usize>,
+        pub(crate) next: Vec<Task>,
     }
 
 
@@ -48,17 +94,62 @@ mod runtime {
         pub(crate) eoi: EndOfInstant<'a>,
         /// The id of the current instant.
         pub(crate) current_instant: usize,
+        /// The nodes compiled at runtime that are not yet in the runtime.
+        pub(crate) new_nodes: Vec<Box<Node<'a, (), Out = ()>>>,
+        /// The nodes compiled at runtime that will replace nodes of the runtime.
+        pub(crate) recycled_nodes: Vec<(usize, Box<Node<'a, (), Out = ()>>)>,
+        /// The number of nodes in the runtime, including `new_nodes`.
+        pub(crate) nb_nodes: usize,
+        /// The id of the main node being executed.
+        pub(crate) current_node: usize,
+        /// The tag of the main node being executed, see `Task`.
+        pub(crate) current_tag: usize,
+        /// The last tag given by `new_tag`.
+        pub(crate) last_tag: usize,
     }
 
 
     impl<'a> SubRuntime<'a> {
         /// Add a new main node to be executed on current instant
         pub fn add_current(&mut self, ind: usize) {
-            self.tasks.current.push(ind);
+            let task = self.task(ind);
+            self.add_current_task(task);
+        }
+        /// Add a new main node to be executed on current instant, like `add_current`
+        /// in sequential mode.
+        pub fn add_current_spread(&mut self, ind: usize) {
+            self.add_current(ind);
         }
         /// Add a new main node to be executed on next instant
         pub fn add_next(&mut self, ind: usize) {
-            self.tasks.next.push(ind);
+            let task = self.task(ind);
+            self.add_next_task(task);
+        }
+        /// Add a task to be executed on current instant, keeping its tag
+        pub fn add_current_task(&mut self, task: Task) {
+            self.tasks.current.push(task);
+        }
+        /// Add a task to be executed on next instant, keeping its tag
+        pub fn add_next_task(&mut self, task: Task) {
+            self.tasks.next.push(task);
+        }
+        /// Returns the task running the main node `ind` in the current execution.
+        pub fn task(&self, ind: usize) -> Task {
+            Task {
+                node: ind,
+                tag: self.current_tag,
+            }
+        }
+        /// Returns the tag of the main node being executed.
+        pub fn get_current_tag(&mut self) -> usize {
+            self.current_tag
+        }
+        /// Takes a new tag, greater than all the previous ones, for the rest of the
+        /// main node being executed.
+        pub fn new_tag(&mut self) -> usize {
+            self.last_tag += 1;
+            self.current_tag = self.last_tag;
+            self.current_tag
         }
         /// Add a new en of instant object
         pub fn add_eoi(&mut self, box_eoi: Box<EndOfInstantCallback<'a>>) {
@@ -67,27 +158,77 @@ mod runtime {
         pub fn get_current_instant(&mut self) -> usize {
             self.current_instant
         }
+        /// Returns the id of the main node being executed.
+        pub fn get_current_node(&mut self) -> usize {
+            self.current_node
+        }
         /// Ask for ending the execution, useless in sequential mode.
         pub fn end(&mut self) {}
+
+        /// Compiles new nodes in the running runtime.
+        ///
+        /// `fill` is given a graph whose ids start after the nodes already in the runtime,
+        /// and returns the id of the node to start from. The nodes are available as soon as
+        /// this function returns.
+        pub(crate) fn add_graph<F>(&mut self, fill: F) -> usize
+        where
+            F: FnOnce(&mut Graph<'a>) -> usize,
+        {
+            self.add_graph_recycling(&mut vec![], fill)
+        }
+
+        /// Same as `add_graph`, but the graph first reuses the ids of `ids`, whose nodes must
+        /// not be able to run anymore. The fresh ids of the graph are then added to `ids`.
+        pub(crate) fn add_graph_recycling<F>(&mut self, ids: &mut Vec<usize>, fill: F) -> usize
+        where
+            F: FnOnce(&mut Graph<'a>) -> usize,
+        {
+            let mut g = Graph::new_recycling(self.nb_nodes, ids.clone());
+            let start = fill(&mut g);
+            let (recycled, nodes) = g.get_recycling();
+            for (pos, n) in recycled {
+                match n {
+                    Some(b) => self.recycled_nodes.push((pos, b)),
+                    None => unreachable!(),
+                }
+            }
+            for n in nodes {
+                match n {
+                    Some(b) => {
+                        ids.push(self.nb_nodes);
+                        self.new_nodes.push(b);
+                        self.nb_nodes += 1;
+                    }
+                    None => unreachable!(),
+                }
+            }
+            start
+        }
     }
 
 
     /// Runtime for running reactive graph.
     ///
     /// It contains all the information needed to execute of a reactive process.
-    pub struct Runtime<'a> {
+    pub struct Runtime<'a, Out = ()> {
         /// The reactive control-flow graph in non-optional version.
         /// See [`Graph`](struct.Graph.html).
         pub(super) nodes: Vec<Box<Node<'a, (), Out = ()>>>,
 
         /// The SubRuntime containing all runtime info.
         sub_runtime: SubRuntime<'a>,
+
+        /// Where the value outputted by the process is stored when it ends.
+        result: RCell<Out>,
     }
 
-    impl<'a> Runtime<'a> {
+    impl<'a, Out: Val<'a>> Runtime<'a, Out> {
         /// Executes the whole reactive process until it ends.
-        pub fn execute(&mut self) {
+        ///
+        /// Returns the value outputted by the process, or `None` if it did not terminate.
+        pub fn execute(&mut self) -> Option<Out> {
             while self.instant() {}
+            self.result.try_get()
         }
         pub fn instantn(&mut self, n: usize) -> bool {
             for _ in 0..n {
@@ -99,14 +240,25 @@ mod runtime {
         }
 
 
+        /// Moves the nodes compiled by the last executed node into the runtime.
+        fn add_new_nodes(&mut self) {
+            self.nodes.append(&mut self.sub_runtime.new_nodes);
+            for (pos, node) in take(&mut self.sub_runtime.recycled_nodes) {
+                self.nodes[pos] = node;
+            }
+        }
+
         /// Executes an single instant of the reactive process loaded in the runtime.
         ///
         /// Returns whether the process should continue.
         pub fn instant(&mut self) -> bool {
             while self.sub_runtime.tasks.current.len() > 0 {
                 let v = take(&mut self.sub_runtime.tasks.current);
-                for i in v {
-                    self.nodes[i].call(&mut self.sub_runtime, ());
+                for task in v {
+                    self.sub_runtime.current_node = task.node;
+                    self.sub_runtime.current_tag = task.tag;
+                    self.nodes[task.node].call(&mut self.sub_runtime, ());
+                    self.add_new_nodes();
                 }
             }
             self.sub_runtime.tasks.current = take(&mut self.sub_runtime.tasks.next);
@@ -114,6 +266,7 @@ mod runtime {
             for eoi in eois {
                 eoi.on_end_of_instant(&mut self.sub_runtime);
             }
+            self.add_new_nodes();
 
             self.sub_runtime.current_instant += 1;
 
@@ -134,8 +287,8 @@ mod runtime {
         /// The graph must be complete i.e any reserved id must not be empty.
         /// If the graph is not complete, it panics.
         /// This function does not setup a start point:
-        fn fromgraph(g: Graph<'a>) -> Self {
-            let mut r = Self::newempty();
+        fn fromgraph(g: Graph<'a>, result: RCell<Out>) -> Self {
+            let mut r = Self::newempty(result);
             for n in g.get() {
                 match n {
                     Some(b) => {
@@ -144,6 +297,7 @@ mod runtime {
                     None => unreachable!(),
                 }
             }
+            r.sub_runtime.nb_nodes = r.nodes.len();
             r
         }
 
@@ -157,18 +311,49 @@ mod runtime {
         /// Normally,types that implement [``][gf] are [`MarkedProcess`][mp]
         pub fn new<GF>(gf: GF) -> Self
         where
-            GF: GraphFiller<'a>,
+            GF: GraphFiller<'a, Out = Out>,
         {
-            let (g,start) = gf.compile_to_graph();
-            let mut r = Runtime::fromgraph(g);
+            let result = RCell::new();
+            let (g,start) = gf.compile_to_graph(result.clone());
+            let mut r = Runtime::fromgraph(g, result);
             r.sub_runtime.add_current(start);
             r
         }
 
+        /// Creates a Runtime like `new`, the number of threads is ignored in sequential mode.
+        pub fn with_threads<GF>(_nb_threads: usize, gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            Runtime::new(gf)
+        }
+
+        /// Creates a Runtime like `new`, the number of threads and the wait policy
+        /// are ignored in sequential mode.
+        pub fn with_wait_policy<GF>(_nb_threads: usize, _wait: WaitPolicy, gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            Runtime::new(gf)
+        }
+
+        /// Creates a Runtime like `new`, the process is always executed on the calling thread
+        /// in sequential mode.
+        pub fn inline<GF>(gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            Runtime::new(gf)
+        }
+
+        /// Returns the number of threads of the runtime, always 1 in sequential mode.
+        pub fn nb_threads(&self) -> usize {
+            1
+        }
 
         /// Creates a new empty runtime.
-        pub(crate) fn newempty() -> Self {
-            Runtime::<'a> {
+        pub(crate) fn newempty(result: RCell<Out>) -> Self {
+            Runtime {
                 nodes: vec![],
                 sub_runtime: SubRuntime {
                     current_instant: 3,
@@ -177,7 +362,14 @@ mod runtime {
                         next: vec![],
                     },
                     eoi: EndOfInstant { pending: vec![] },
+                    new_nodes: vec![],
+                    recycled_nodes: vec![],
+                    nb_nodes: 0,
+                    current_node: 0,
+                    current_tag: 0,
+                    last_tag: 0,
                 },
+                result,
             }
         }
     }
@@ -200,11 +392,18 @@ mod runtime {
     use std::sync::atomic::AtomicUsize;
     use std::sync::atomic::AtomicBool;
     use std::sync::atomic::Ordering::*;
-    use std::mem;
-    use std::ptr;
     use std::sync::Arc;
+    use std::sync::{RwLock, Mutex, Condvar};
+    use std::sync::atomic::fence;
+    use std::sync::mpsc::{channel, Sender, Receiver};
+    use std::thread::{self, JoinHandle};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::mem;
 
-    const NB_THREADS: usize = 4;
+    /// Returns the number of threads used by `Runtime::new`, the number of cores of the machine.
+    pub fn default_nb_threads() -> usize {
+        ::num_cpus::get()
+    }
 
 //  _   _           _       ____     _ _
 // | \ | | ___   __| | ___ / ___|___| | |
@@ -248,6 +447,26 @@ mod runtime {
 
     use self::node_cell::*;
 
+    /// The nodes compiled while the runtime is running, shared by all threads.
+    ///
+    /// Their ids start at `base`, the number of nodes of the initial graph.
+    pub(crate) struct DynNodes<'a> {
+        base: usize,
+        nodes: RwLock<Vec<Arc<NodeCell<'a>>>>,
+    }
+
+    impl<'a> DynNodes<'a> {
+        fn new(base: usize) -> Self {
+            DynNodes {
+                base,
+                nodes: RwLock::new(vec![]),
+            }
+        }
+        fn get(&self, num: usize) -> Arc<NodeCell<'a>> {
+            self.nodes.read().unwrap()[num - self.base].clone()
+        }
+    }
+
 
 
 //  ___           _              _   ____        _
@@ -262,19 +481,105 @@ mod runtime {
         pub(crate) ws: Box<WorkStealing>,
         /// The number of threads that have finished this instant.
         pub(crate) nbf: Arc<AtomicUsize>,
+        /// The tasks given to the threads by the other ones, shared by all threads.
+        pub(crate) inbox: Arc<Inboxes>,
+    }
+
+    /// The tasks given to each thread by the other ones during an instant,
+    /// see `SubRuntime::add_current_spread`.
+    ///
+    /// A thread only finishes an instant when all the queues are empty, and a finished thread
+    /// counts itself as running again before taking tasks from them, like when it steals.
+    /// So all the queues are empty when all the threads have finished the instant.
+    pub(crate) struct Inboxes {
+        queues: Vec<Mutex<Vec<Task>>>,
+        /// The number of tasks in all the queues.
+        len: AtomicUsize,
+    }
+
+    impl Inboxes {
+        fn new(nb_threads: usize) -> Self {
+            Inboxes {
+                queues: (0..nb_threads).map(|_| Mutex::new(vec![])).collect(),
+                len: AtomicUsize::new(0),
+            }
+        }
+
+        /// Gives `task` to the thread `thread`.
+        fn push(&self, thread: usize, task: Task) {
+            let mut queue = self.queues[thread].lock().unwrap();
+            queue.push(task);
+            self.len.fetch_add(1, SeqCst);
+        }
+
+        fn is_empty(&self) -> bool {
+            self.len.load(SeqCst) == 0
+        }
+
+        /// Takes all the tasks of the queues, starting with the ones given to `thread`.
+        fn take_all(&self, thread: usize) -> Vec<Task> {
+            let nb_threads = self.queues.len();
+            let mut tasks = vec![];
+            for i in 0..nb_threads {
+                let mut queue = self.queues[(thread + i) % nb_threads].lock().unwrap();
+                self.len.fetch_sub(queue.len(), SeqCst);
+                tasks.append(&mut queue);
+            }
+            tasks
+        }
+    }
+
+    /// Lets the threads waiting at the instant barrier block until they may have something to do.
+    pub(crate) struct Sleep {
+        /// The number of parked threads.
+        sleepers: AtomicUsize,
+        lock: Mutex<()>,
+        cvar: Condvar,
+    }
+
+    impl Sleep {
+        fn new() -> Self {
+            Sleep {
+                sleepers: AtomicUsize::new(0),
+                lock: Mutex::new(()),
+                cvar: Condvar::new(),
+            }
+        }
+
+        /// Parks the thread until `wake` is called, unless `ready` already holds.
+        fn park<F: Fn() -> bool>(&self, ready: F) {
+            let guard = self.lock.lock().unwrap();
+            self.sleepers.fetch_add(1, SeqCst);
+            if !ready() {
+                let _guard = self.cvar.wait(guard).unwrap();
+            }
+            self.sleepers.fetch_sub(1, SeqCst);
+        }
+
+        /// Wakes the parked threads, if any.
+        fn wake(&self) {
+            // Orders the caller's writes before reading the number of sleepers,
+            // a thread cannot be parking without seeing them.
+            fence(SeqCst);
+            if self.sleepers.load(SeqCst) > 0 {
+                let _guard = self.lock.lock().unwrap();
+                self.cvar.notify_all();
+            }
+        }
     }
 
     /// Contains access to the work-stealing system of a given instant.
     pub(crate) struct WorkStealing {
-        pub(crate) deque: Deque<usize>,
-        pub(crate) stealers: [Stealer<usize>; NB_THREADS - 1],
+        pub(crate) deque: Deque<Task>,
+        /// The stealers of the deques of the other threads.
+        pub(crate) stealers: Vec<Stealer<Task>>,
     }
 
     impl WorkStealing {
-        fn new(deque: Deque<usize>, vstealers: Vec<Stealer<usize>>) -> WorkStealing {
+        fn new(deque: Deque<Task>, stealers: Vec<Stealer<Task>>) -> WorkStealing {
             WorkStealing {
                 deque,
-                stealers: vec2array!(vstealers, NB_THREADS - 1),
+                stealers,
             }
         }
     }
@@ -305,10 +610,42 @@ mod runtime {
 
         /// When set to true, this is the end of the global process.
         pub(crate) aend: Arc<AtomicBool>,
+
+        /// The nodes compiled at runtime.
+        pub(crate) dyn_nodes: Arc<DynNodes<'a>>,
+
+        /// The id of the main node being executed.
+        pub(crate) current_node: usize,
+
+        /// How to wait at the instant barrier.
+        pub(crate) wait: WaitPolicy,
+
+        /// Where the threads are parked at the instant barrier.
+        pub(crate) sleep: Arc<Sleep>,
+
+        /// The tag of the main node being executed, see `Task`.
+        pub(crate) current_tag: usize,
+
+        /// The last tag given by `new_tag`, shared by all threads.
+        pub(crate) last_tag: Arc<AtomicUsize>,
+
+        /// The index of the thread running this `SubRuntime`.
+        thread: usize,
+
+        /// The last thread given a task by `add_current_spread`.
+        spread: usize,
     }
 
     impl<'a> SubRuntime<'a> {
-        fn new(mut ids: Vec<InstantData>, aend: Arc<AtomicBool>) -> Self {
+        fn new(
+            thread: usize,
+            mut ids: Vec<InstantData>,
+            aend: Arc<AtomicBool>,
+            dyn_nodes: Arc<DynNodes<'a>>,
+            wait: WaitPolicy,
+            sleep: Arc<Sleep>,
+            last_tag: Arc<AtomicUsize>,
+        ) -> Self {
             assert_eq!(ids.len(), 3);
             // start instant must be 3
             let previous = ids.pop().unwrap();
@@ -321,16 +658,68 @@ mod runtime {
                 eoi: vec![],
                 current_instant: 3,
                 aend,
+                dyn_nodes,
+                current_node: 0,
+                wait,
+                sleep,
+                current_tag: 0,
+                last_tag,
+                thread,
+                spread: thread,
             }
 
         }
         /// Add a new main node to be executed on current instant
         pub fn add_current(&mut self, ind: usize) {
-            self.current.ws.deque.push(ind);
+            let task = self.task(ind);
+            self.add_current_task(task);
+        }
+        /// Add a new main node to be executed on current instant, giving it to the threads
+        /// in turn instead of waiting for them to steal it.
+        pub fn add_current_spread(&mut self, ind: usize) {
+            let task = self.task(ind);
+            self.spread = (self.spread + 1) % self.current.inbox.queues.len();
+            if self.spread == self.thread {
+                self.current.ws.deque.push(task);
+            } else {
+                self.current.inbox.push(self.spread, task);
+            }
+            if self.wait.park {
+                self.sleep.wake();
+            }
         }
         /// Add a new main node to be executed on next instant
         pub fn add_next(&mut self, ind: usize) {
-            self.next.ws.deque.push(ind);
+            let task = self.task(ind);
+            self.add_next_task(task);
+        }
+        /// Add a task to be executed on current instant, keeping its tag
+        pub fn add_current_task(&mut self, task: Task) {
+            self.current.ws.deque.push(task);
+            if self.wait.park {
+                self.sleep.wake();
+            }
+        }
+        /// Add a task to be executed on next instant, keeping its tag
+        pub fn add_next_task(&mut self, task: Task) {
+            self.next.ws.deque.push(task);
+        }
+        /// Returns the task running the main node `ind` in the current execution.
+        pub fn task(&self, ind: usize) -> Task {
+            Task {
+                node: ind,
+                tag: self.current_tag,
+            }
+        }
+        /// Returns the tag of the main node being executed.
+        pub fn get_current_tag(&mut self) -> usize {
+            self.current_tag
+        }
+        /// Takes a new tag, greater than all the previous ones of all threads, for the rest
+        /// of the main node being executed.
+        pub fn new_tag(&mut self) -> usize {
+            self.current_tag = self.last_tag.fetch_add(1, SeqCst) + 1;
+            self.current_tag
         }
         /// Add a new en of instant object
         pub fn add_eoi(&mut self, box_eoi: Box<EndOfInstantCallback<'a>>) {
@@ -339,11 +728,58 @@ mod runtime {
         pub fn get_current_instant(&mut self) -> usize {
             self.current_instant
         }
+        /// Returns the id of the main node being executed.
+        pub fn get_current_node(&mut self) -> usize {
+            self.current_node
+        }
         /// Ask for ending the execution, all threads will stop at the end of this instant.
         pub fn end(&mut self) {
             //println!("END\n");
             self.aend.store(true, Relaxed);
         }
+
+        /// Compiles new nodes in the running runtime.
+        ///
+        /// `fill` is given a graph whose ids start after the nodes already in the runtime,
+        /// and returns the id of the node to start from. The nodes are available to all
+        /// threads as soon as this function returns.
+        pub(crate) fn add_graph<F>(&mut self, fill: F) -> usize
+        where
+            F: FnOnce(&mut Graph<'a>) -> usize,
+        {
+            self.add_graph_recycling(&mut vec![], fill)
+        }
+
+        /// Same as `add_graph`, but the graph first reuses the ids of `ids`, whose nodes must
+        /// not be able to run anymore. The fresh ids of the graph are then added to `ids`.
+        pub(crate) fn add_graph_recycling<F>(&mut self, ids: &mut Vec<usize>, fill: F) -> usize
+        where
+            F: FnOnce(&mut Graph<'a>) -> usize,
+        {
+            let base = self.dyn_nodes.base;
+            let mut nodes = self.dyn_nodes.nodes.write().unwrap();
+            let mut g = Graph::new_recycling(base + nodes.len(), ids.clone());
+            let start = fill(&mut g);
+            let (recycled, fresh) = g.get_recycling();
+            for (pos, n) in recycled {
+                match n {
+                    Some(b) => {
+                        nodes[pos - base] = Arc::new(NodeCell::new(b));
+                    }
+                    None => unreachable!(),
+                }
+            }
+            for n in fresh {
+                match n {
+                    Some(b) => {
+                        ids.push(base + nodes.len());
+                        nodes.push(Arc::new(NodeCell::new(b)));
+                    }
+                    None => unreachable!(),
+                }
+            }
+            start
+        }
     }
 
 //  _____ _                        _ ____              _   _
@@ -358,22 +794,35 @@ mod runtime {
     pub struct ThreadRuntime<'a> {
         pub(super) sub: SubRuntime<'a>,
         pub(super) nodes: Arc<Vec<NodeCell<'a>>>,
+        /// The number of threads of the runtime.
+        pub(super) nb_threads: usize,
     }
 
 
     impl<'a> ThreadRuntime<'a> {
-        fn new(ids: Vec<InstantData>, end: Arc<AtomicBool>, nodes: Arc<Vec<NodeCell<'a>>>) -> Self {
+        fn new(
+            thread: usize,
+            ids: Vec<InstantData>,
+            end: Arc<AtomicBool>,
+            nodes: Arc<Vec<NodeCell<'a>>>,
+            dyn_nodes: Arc<DynNodes<'a>>,
+            nb_threads: usize,
+            wait: WaitPolicy,
+            sleep: Arc<Sleep>,
+            last_tag: Arc<AtomicUsize>,
+        ) -> Self {
             ThreadRuntime {
-                sub: SubRuntime::new(ids, end),
+                sub: SubRuntime::new(thread, ids, end, dyn_nodes, wait, sleep, last_tag),
                 nodes,
+                nb_threads,
             }
 
         }
 
         /// Change formally of instant (switch previous, current and next)
-        /// The synchronization signal is when current.nbf gets to NB_THREADS.
-        /// It cannot go down if it reaches NB_THREADS at any point of time because
-        /// reaching NB_THREADS means
+        /// The synchronization signal is when current.nbf gets to nb_threads.
+        /// It cannot go down if it reaches nb_threads at any point of time because
+        /// reaching nb_threads means
         fn step(&mut self) {
             self.sub.previous.nbf.store(0, Relaxed);
             swap3(
@@ -384,30 +833,53 @@ mod runtime {
             self.sub.current_instant += 1;
         }
         /// run a node by id
-        fn run_node(&mut self, num: usize) {
-            self.nodes[num].call(&mut self.sub);
+        fn run_node(&mut self, task: Task) {
+            let num = task.node;
+            self.sub.current_node = num;
+            self.sub.current_tag = task.tag;
+            if num < self.nodes.len() {
+                self.nodes[num].call(&mut self.sub);
+            } else {
+                let node = self.sub.dyn_nodes.get(num);
+                node.call(&mut self.sub);
+            }
         }
 
         /// Runs the nodes of an instant by work stealing, then synchronize with other threads,
         /// for changing instant then run the eoi routines.
         fn instant(&mut self) {
             'instant: loop {
-                while let Some(nb) = self.sub.current.ws.deque.pop() {
-                    self.run_node(nb);
+                loop {
+                    while let Some(nb) = self.sub.current.ws.deque.pop() {
+                        self.run_node(nb);
+                    }
+                    if self.sub.current.inbox.is_empty() {
+                        break;
+                    }
+                    self.take_inbox();
                 }
-                self.sub.current.nbf.fetch_add(1, SeqCst);
-                while self.sub.current.nbf.load(SeqCst) < NB_THREADS {
-                    for i in 0..NB_THREADS - 1 {
+                self.finish();
+                let mut round = 0;
+                while self.sub.current.nbf.load(SeqCst) < self.nb_threads {
+                    for i in 0..self.nb_threads - 1 {
                         if !self.sub.current.ws.stealers[i].is_empty() {
                             self.sub.current.nbf.fetch_sub(1, SeqCst);
                             if let Steal::Data(nb) = self.sub.current.ws.stealers[i].steal() {
                                 self.run_node(nb);
                                 continue 'instant;
                             }
-                            self.sub.current.nbf.fetch_add(1, SeqCst);
+                            self.finish();
+                        }
+                    }
+                    if !self.sub.current.inbox.is_empty() {
+                        self.sub.current.nbf.fetch_sub(1, SeqCst);
+                        if self.take_inbox() {
+                            continue 'instant;
                         }
+                        self.finish();
                     }
-                    cpu_pause();
+                    self.wait(round);
+                    round += 1;
                 }
                 break 'instant;
             } // end 'instant
@@ -418,6 +890,53 @@ mod runtime {
             }
 
         }
+        /// Moves the tasks given to the threads to the deque of this thread,
+        /// returns whether there was any.
+        fn take_inbox(&mut self) -> bool {
+            let tasks = self.sub.current.inbox.take_all(self.sub.thread);
+            let found = !tasks.is_empty();
+            for task in tasks {
+                self.sub.current.ws.deque.push(task);
+            }
+            found
+        }
+
+        /// Counts this thread as finished for the current instant,
+        /// the last thread to finish wakes the parked ones.
+        fn finish(&self) {
+            let nbf = self.sub.current.nbf.fetch_add(1, SeqCst) + 1;
+            if nbf == self.nb_threads && self.sub.wait.park {
+                self.sub.sleep.wake();
+            }
+        }
+
+        /// Waits for the `round`-th time at the instant barrier, according to the wait policy.
+        fn wait(&self, round: usize) {
+            let wait = self.sub.wait;
+            if round < wait.spins {
+                cpu_pause();
+            } else if round - wait.spins < wait.yields || !wait.park {
+                thread::yield_now();
+            } else {
+                let current = &self.sub.current;
+                let nb_threads = self.nb_threads;
+                self.sub.sleep.park(|| {
+                    current.nbf.load(SeqCst) >= nb_threads
+                        || current.ws.stealers.iter().any(|s| !s.is_empty())
+                        || !current.inbox.is_empty()
+                });
+            }
+        }
+
+        /// Opens the instant barrier after a panic of this thread, so that the other threads
+        /// do not wait for it forever: the end flag is raised, the current instant is marked
+        /// as finished by all the threads, and the parked threads are woken.
+        fn poison(&self) {
+            self.sub.aend.store(true, SeqCst);
+            self.sub.current.nbf.store(self.nb_threads, SeqCst);
+            self.sub.sleep.wake();
+        }
+
         /// Runs `n` instant or until end flag is raised
         fn instantn(&mut self, n: usize) {
             for _ in 0..n {
@@ -443,48 +962,120 @@ mod runtime {
 // |  _ <| |_| | | | | |_| | | | | | |  __/
 // |_| \_\\__,_|_| |_|\__|_|_| |_| |_|\___|
 
+    /// Orders sent by the runtime to its worker threads.
+    #[derive(Clone, Copy)]
+    enum Order {
+        Instant,
+        Instantn(usize),
+        Execute,
+    }
+
+    /// The loop of a worker thread: it parks until an order arrives, runs it on its
+    /// `ThreadRuntime`, and reports to the runtime. It stops when the runtime is dropped.
+    fn work(
+        mut tr: ThreadRuntime<'static>,
+        orders: Receiver<Order>,
+        done: Sender<thread::Result<()>>,
+    ) {
+        while let Ok(order) = orders.recv() {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| match order {
+                Order::Instant => tr.instant(),
+                Order::Instantn(n) => tr.instantn(n),
+                Order::Execute => tr.execute(),
+            }));
+            if res.is_err() {
+                tr.poison();
+            }
+            if done.send(res).is_err() {
+                break;
+            }
+        }
+    }
+
     /// Runtime for running reactive graph.
     ///
     /// It contains all the information needed to execute of a reactive process.
-    pub struct Runtime<'a> {
+    /// The worker threads are created with the runtime and live until it is dropped.
+    pub struct Runtime<'a, Out = ()> {
         /// The reactive control-flow graph in non-optional version.
         /// See [`Graph`](struct.Graph.html).
         #[allow(unused)]
         pub(super) nodes: Arc<Vec<NodeCell<'a>>>,
 
-        /// The SubRuntime containing all runtime info.
-        thread_runtimes: Vec<ThreadRuntime<'a>>,
+        /// The channels used to give orders to the worker threads.
+        orders: Vec<Sender<Order>>,
+
+        /// The channel where the worker threads report the end of an order.
+        done: Receiver<thread::Result<()>>,
+
+        /// The worker threads, each one runs a `ThreadRuntime`.
+        workers: Vec<JoinHandle<()>>,
+
+        /// The only `ThreadRuntime` of a runtime executed on the calling thread,
+        /// there is no worker thread then.
+        inline: Option<ThreadRuntime<'a>>,
 
         /// TODO doc
         pub(crate) end: Arc<AtomicBool>,
+
+        /// Whether a worker thread panicked, the runtime cannot run anymore then.
+        poisoned: bool,
+
+        /// Where the value outputted by the process is stored when it ends.
+        result: RCell<Out>,
     }
 
-    impl<'a> Runtime<'a> {
+    impl<'a, Out: Val<'a>> Runtime<'a, Out> {
         /// Executes the whole reactive process until it ends.
-        pub fn execute(&mut self) {
-            crossbeam::scope(|scope| for tr in self.thread_runtimes.iter_mut() {
-                scope.spawn(move || tr.execute());
-            });
+        ///
+        /// Returns the value outputted by the process, or `None` if it did not terminate.
+        pub fn execute(&mut self) -> Option<Out> {
+            self.run_workers(Order::Execute);
             //assert!(self.end.load(SeqCst));
-
+            self.result.try_get()
         }
 
         /// Executes an single instant of the reactive process loaded in the runtime.
         ///
         /// Returns whether the process should continue.
         pub fn instant(&mut self) -> bool {
-            crossbeam::scope(|scope| for tr in self.thread_runtimes.iter_mut() {
-                scope.spawn(move || tr.instant());
-            });
+            self.run_workers(Order::Instant);
             !self.end.load(SeqCst)
         }
         pub fn instantn(&mut self, n: usize) -> bool {
-            crossbeam::scope(|scope| for tr in self.thread_runtimes.iter_mut() {
-                scope.spawn(move || tr.instantn(n));
-            });
+            self.run_workers(Order::Instantn(n));
             !self.end.load(SeqCst)
         }
 
+        /// Wakes the worker threads with `order` and waits until all of them are done.
+        ///
+        /// A panic in a worker thread is resumed in the calling thread,
+        /// and any later use of the runtime panics.
+        fn run_workers(&mut self, order: Order) {
+            assert!(!self.poisoned, "the runtime was poisoned by a panic of a worker thread");
+            if let Some(ref mut tr) = self.inline {
+                match order {
+                    Order::Instant => tr.instant(),
+                    Order::Instantn(n) => tr.instantn(n),
+                    Order::Execute => tr.execute(),
+                }
+                return;
+            }
+            for orders in self.orders.iter() {
+                orders.send(order).expect("a worker thread has stopped");
+            }
+            let mut res = Ok(());
+            for _ in 0..self.orders.len() {
+                if let Err(e) = self.done.recv().expect("a worker thread has stopped") {
+                    res = Err(e);
+                }
+            }
+            if let Err(e) = res {
+                self.poisoned = true;
+                panic::resume_unwind(e);
+            }
+        }
+
         // pub fn printDot(&mut self) {
         //     println!("digraph {{");
         //     let mut cfgd = CFGDrawer::new();
@@ -499,7 +1090,14 @@ mod runtime {
         /// The graph must be complete i.e any reserved id must not be empty.
         /// If the graph is not complete, it panics.
         /// This function does not setup a start point:
-        fn fromgraph(g: Graph<'a>) -> Self {
+        fn fromgraph(
+            g: Graph<'a>,
+            nb_threads: usize,
+            wait: WaitPolicy,
+            start: usize,
+            inline: bool,
+            result: RCell<Out>,
+        ) -> Self {
             let mut r = vec![];
             for n in g.get() {
                 match n {
@@ -509,7 +1107,7 @@ mod runtime {
                     None => unreachable!(),
                 }
             }
-            Runtime::fromnodes(r)
+            Runtime::fromnodes(r, nb_threads, wait, start, inline, result)
         }
 
 
@@ -520,27 +1118,81 @@ mod runtime {
         ///
         /// After this function, the runtime is ready to be used
         /// Normally,types that implement [``][gf] are [`MarkedProcess`][mp]
+        ///
+        /// It runs on one thread per core of the machine, see `default_nb_threads`.
         pub fn new<GF>(gf: GF) -> Self
         where
-            GF: GraphFiller<'a>,
+            GF: GraphFiller<'a, Out = Out>,
         {
-            let (g,start) = gf.compile_to_graph();
-            let mut r = Runtime::fromgraph(g);
-            r.thread_runtimes[0].sub.add_current(start);
-            r
+            Runtime::with_threads(default_nb_threads(), gf)
         }
 
-        /// Creates a new empty runtime.
-        fn fromnodes(nodes: Vec<NodeCell<'a>>) -> Self {
-            let deques: Vec<Vec<Deque<usize>>> = (0..NB_THREADS)
+        /// Creates a Runtime running the process on `nb_threads` threads.
+        ///
+        /// `nb_threads` must be at least 1, otherwise it panics.
+        pub fn with_threads<GF>(nb_threads: usize, gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            Runtime::with_wait_policy(nb_threads, WaitPolicy::default(), gf)
+        }
+
+        /// Creates a Runtime running the process on `nb_threads` threads,
+        /// that wait at the instant barrier according to `wait`.
+        ///
+        /// `nb_threads` must be at least 1, otherwise it panics.
+        pub fn with_wait_policy<GF>(nb_threads: usize, wait: WaitPolicy, gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            assert!(nb_threads > 0, "a runtime needs at least one thread");
+            let result = RCell::new();
+            let (g,start) = gf.compile_to_graph(result.clone());
+            Runtime::fromgraph(g, nb_threads, wait, start, false, result)
+        }
+
+        /// Creates a Runtime executing the process on the calling thread, without any
+        /// worker thread.
+        ///
+        /// It is the same `Runtime` type as the one built by `new`, running the same `Send`
+        /// processes, so both can be chosen at runtime, e.g. to run small workloads inline.
+        /// It still uses the synchronized structures of the parallel engine.
+        pub fn inline<GF>(gf: GF) -> Self
+        where
+            GF: GraphFiller<'a, Out = Out>,
+        {
+            let result = RCell::new();
+            let (g,start) = gf.compile_to_graph(result.clone());
+            Runtime::fromgraph(g, 1, WaitPolicy::spin(), start, true, result)
+        }
+
+        /// Returns the number of threads of the runtime.
+        pub fn nb_threads(&self) -> usize {
+            if self.inline.is_some() {
+                1
+            } else {
+                self.workers.len()
+            }
+        }
+
+        /// Creates a new runtime starting from the node `start`, and spawns its worker threads.
+        fn fromnodes(
+            nodes: Vec<NodeCell<'a>>,
+            nb_threads: usize,
+            wait: WaitPolicy,
+            start: usize,
+            inline: bool,
+            result: RCell<Out>,
+        ) -> Self {
+            let deques: Vec<Vec<Deque<Task>>> = (0..nb_threads)
                 .map(|_| (0..3).map(|_| Deque::new()).collect())
                 .collect();
 
-            let stealers: Vec<Vec<Vec<Stealer<usize>>>> = (0..NB_THREADS)
+            let stealers: Vec<Vec<Vec<Stealer<Task>>>> = (0..nb_threads)
                 .map(|th| {
                     (0..3)
                         .map(|inst| {
-                            (0..NB_THREADS)
+                            (0..nb_threads)
                                 .filter(|sth| *sth != th)
                                 .map(|sth| deques[sth][inst].stealer())
                                 .collect()
@@ -566,43 +1218,158 @@ mod runtime {
                 Arc::new(AtomicUsize::new(0)),
             ];
 
-            let nb_finishs: Vec<Vec<Arc<AtomicUsize>>> = (0..NB_THREADS)
+            let nb_finishs: Vec<Vec<Arc<AtomicUsize>>> = (0..nb_threads)
                 .map(|_| {
                     (0..3).map(|inst| nb_finish_base[inst].clone()).collect()
                 })
                 .collect();
 
+            let inbox_base: Vec<Arc<Inboxes>> =
+                (0..3).map(|_| Arc::new(Inboxes::new(nb_threads))).collect();
+
             let instdatas: Vec<Vec<InstantData>> = workStealings
                 .into_iter()
                 .zip(nb_finishs.into_iter())
                 .map(|(wss, nbfs)| {
                     wss.into_iter()
                         .zip(nbfs.into_iter())
-                        .map(|(ws, nbf)| InstantData { ws, nbf })
+                        .zip(inbox_base.iter())
+                        .map(|((ws, nbf), inbox)| InstantData { ws, nbf, inbox: inbox.clone() })
                         .collect()
                 })
                 .collect();
 
             let end = Arc::new(AtomicBool::new(false));
+            let dyn_nodes = Arc::new(DynNodes::new(nodes.len()));
+            let sleep = Arc::new(Sleep::new());
+            let last_tag = Arc::new(AtomicUsize::new(0));
             let arc_nodes = Arc::new(nodes);
 
-            let subs: Vec<ThreadRuntime<'a>> = instdatas
+            let mut subs: Vec<ThreadRuntime<'a>> = instdatas
                 .into_iter()
-                .map(|ids| {
-                    ThreadRuntime::new(ids, end.clone(), arc_nodes.clone())
+                .enumerate()
+                .map(|(thread, ids)| {
+                    ThreadRuntime::new(
+                        thread,
+                        ids,
+                        end.clone(),
+                        arc_nodes.clone(),
+                        dyn_nodes.clone(),
+                        nb_threads,
+                        wait,
+                        sleep.clone(),
+                        last_tag.clone(),
+                    )
                 })
                 .collect();
+            subs[0].sub.add_current(start);
+
+            let (done_sender, done) = channel();
+            if inline {
+                assert_eq!(subs.len(), 1);
+                return Runtime {
+                    end,
+                    nodes: arc_nodes,
+                    orders: vec![],
+                    done,
+                    workers: vec![],
+                    inline: subs.pop(),
+                    poisoned: false,
+                    result,
+                };
+            }
+
+            let mut orders = vec![];
+            let mut workers = vec![];
+            for tr in subs {
+                // SAFETY: a worker thread only touches the data borrowed for 'a while running
+                // an order, and `run_workers` blocks until every worker has reported the end
+                // of its order, with the runtime mutably borrowed. Between orders, the workers
+                // are blocked on their order channel. Dropping the runtime closes the channels
+                // and joins them, so they stop before 'a ends. If the runtime is leaked
+                // (e.g. by `mem::forget`), the channels are never closed: the workers stay
+                // blocked forever and never touch the data again, nor drop it.
+                let tr: ThreadRuntime<'static> = unsafe { mem::transmute(tr) };
+                let (order_sender, order_receiver) = channel();
+                let done_sender = done_sender.clone();
+                orders.push(order_sender);
+                workers.push(thread::spawn(move || work(tr, order_receiver, done_sender)));
+            }
 
             Runtime {
                 end,
-                thread_runtimes: subs,
                 nodes: arc_nodes,
+                orders,
+                done,
+                workers,
+                inline: None,
+                poisoned: false,
+                result,
             }
 
 
 
         }
     }
+
+    impl<'a, Out> Drop for Runtime<'a, Out> {
+        fn drop(&mut self) {
+            // Closing the order channels stops the worker threads.
+            self.orders.clear();
+            for worker in self.workers.drain(..) {
+                let _ = worker.join();
+            }
+        }
+    }
 }
 
 pub use self::runtime::*;
+
+/// Runtime executing the process on the calling thread.
+#[cfg(not(feature = "par"))]
+pub type SeqRuntime<'a, Out = ()> = Runtime<'a, Out>;
+
+/// Runtime executing the process on the calling thread, without any worker thread.
+///
+/// It runs the same `Send` processes as `ParRuntime`, so a program built with `par` can
+/// choose between them at runtime, e.g. to run small workloads on the calling thread.
+/// It is built on the parallel engine (see `Runtime::inline`), and still uses its
+/// synchronized structures.
+#[cfg(feature = "par")]
+pub struct SeqRuntime<'a, Out = ()>(Runtime<'a, Out>);
+
+#[cfg(feature = "par")]
+impl<'a, Out: Val<'a>> SeqRuntime<'a, Out> {
+    /// Creates a runtime executing the process on the calling thread.
+    pub fn new<GF>(gf: GF) -> Self
+    where
+        GF: GraphFiller<'a, Out = Out>,
+    {
+        SeqRuntime(Runtime::inline(gf))
+    }
+
+    /// Executes the whole reactive process until it ends, see `Runtime::execute`.
+    pub fn execute(&mut self) -> Option<Out> {
+        self.0.execute()
+    }
+
+    /// Executes an single instant of the reactive process, see `Runtime::instant`.
+    pub fn instant(&mut self) -> bool {
+        self.0.instant()
+    }
+
+    /// Executes `n` instants of the reactive process, see `Runtime::instantn`.
+    pub fn instantn(&mut self, n: usize) -> bool {
+        self.0.instantn(n)
+    }
+
+    /// Returns the number of threads of the runtime, always 1.
+    pub fn nb_threads(&self) -> usize {
+        1
+    }
+}
+
+/// Runtime executing the process on a pool of worker threads.
+/// Build it with `Runtime::new`, `Runtime::with_threads` or `Runtime::with_wait_policy`.
+#[cfg(feature = "par")]
+pub type ParRuntime<'a, Out = ()> = Runtime<'a, Out>;
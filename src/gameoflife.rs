@@ -71,10 +71,7 @@ impl Board {
         let mut signals = vec![vec![]];
         for i in 0..height {
             for _ in 0..width {
-                let gather = box |emit_value: usize, value: &mut usize| {
-                    *value += emit_value;
-                };
-                signals[i].push(CellSignal::new_mc(0, gather));
+                signals[i].push(CellSignal::new_sum());
             }
             signals.push(vec![]);
         }
@@ -1,5 +1,5 @@
-
 use node::*;
+use super::*;
 /// This type represent a full control-flow graph of a reactive system.
 ///
 /// This is the result of the compilation and will be directly run in the runtime.
@@ -10,23 +10,103 @@ use node::*;
 /// Reserved values may only be used during the compilation process but not during the runtime
 /// (The Runtime type store the same vector but without option).
 /// see [Runtime::fromgraph](struct.Runtime.html#method.fromgraph).
+///
+/// A graph compiled while the runtime is running has its ids starting at `base`,
+/// after all the nodes already present in the runtime. It may also reuse the ids of nodes
+/// of the runtime that are no longer needed, see
+/// [`new_recycling`](struct.Graph.html#method.new_recycling).
 pub struct Graph<'a> {
     nodes: Vec<Option<Box<Node<'a, (), Out = ()>>>>,
+    /// The id of the first node of the graph.
+    base: usize,
+    /// Ids of the runtime that can be reserved before the fresh ones.
+    free: Vec<usize>,
+    /// The nodes placed at ids taken from `free`.
+    recycled: Vec<(usize, Option<Box<Node<'a, (), Out = ()>>>)>,
+    /// The wrappers currently applied to the nodes that are set in the graph.
+    /// See [`push_wrapper`](struct.Graph.html#method.push_wrapper).
+    wrappers: Vec<Box<NodeWrapper<'a> + 'a>>,
+}
+
+/// Trait for objects that encapsulate the main nodes of a sub-graph while it is compiled.
+///
+/// It is used by preemption constructs to control whether a node has the right to run.
+pub(crate) trait NodeWrapper<'a>: Val<'a> {
+    /// Wraps the node `node` that will be placed at id `pos`.
+    fn wrap(&self, pos: usize, node: Box<Node<'a, (), Out = ()>>) -> Box<Node<'a, (), Out = ()>>;
+
+    /// Clones the wrapper, to apply it again on a graph compiled at runtime.
+    fn clone_wrapper(&self) -> Box<NodeWrapper<'a> + 'a>;
+
+    /// Returns whether a task of tag `tag` would still be allowed to run by this wrapper.
+    fn alive(&self, tag: usize) -> bool;
+
+    /// Registers `hook` to be run when the wrapped sub-graph is killed, if it can be.
+    fn on_kill(&self, _hook: Box<KillHook>) {}
+}
+
+/// Trait for the cleanups to run when a sub-graph is killed by a preemption,
+/// see [`on_kill`](struct.Graph.html#method.on_kill).
+pub(crate) trait KillHook: OptSend {
+    /// Runs the cleanup.
+    fn killed(&self);
+
+    /// Returns whether the hook has become useless and may be dropped.
+    fn dropped(&self) -> bool;
+}
+
+/// The wrappers applied to a sub-graph, used to know whether a registration made by one of
+/// its tasks may still wake up a live node.
+pub(crate) struct Scope<'a>(Vec<Box<NodeWrapper<'a> + 'a>>);
+
+impl<'a> Clone for Scope<'a> {
+    fn clone(&self) -> Self {
+        Scope(self.0.iter().map(|w| w.clone_wrapper()).collect())
+    }
+}
+
+impl<'a> Scope<'a> {
+    /// Returns whether a task of tag `tag` would be allowed to run by all the wrappers.
+    pub(crate) fn alive(&self, tag: usize) -> bool {
+        self.0.iter().all(|w| w.alive(tag))
+    }
 }
 
 impl<'a> Graph<'a> {
 
     /// Creates an empty graph.
     pub(crate) fn new() -> Self {
+        Graph::new_at(0)
+    }
+
+    /// Creates an empty graph whose first id will be `base`.
+    pub(crate) fn new_at(base: usize) -> Self {
+        Graph::new_recycling(base, vec![])
+    }
+
+    /// Creates an empty graph whose first id will be `base`, that reserves the ids of `free`
+    /// before the fresh ones.
+    ///
+    /// The nodes previously at these ids are replaced when the graph is added to the runtime,
+    /// so they must not be able to run anymore.
+    pub(crate) fn new_recycling(base: usize, free: Vec<usize>) -> Self {
         Graph {
             nodes: vec![],
+            base,
+            free,
+            recycled: vec![],
+            wrappers: vec![],
         }
     }
 
     /// Reserves a fresh id and returns it
     pub(crate) fn reserve(&mut self) -> usize {
+        if let Some(pos) = self.free.pop() {
+            self.recycled.push((pos, None));
+            return pos;
+        }
         self.nodes.push(None);
-        self.nodes.len() - 1
+        self.base + self.nodes.len() - 1
     }
 
     /// Sets a Node at a given position.
@@ -34,10 +114,19 @@ impl<'a> Graph<'a> {
     /// Sets a Node at a position reserved by [`reserve`](struct.Graph.html#method.reserve).
     /// If the position is not valid (it was never reserved or it has already been set), it panics.
     pub(crate) fn set(&mut self, pos: usize, val: Box<Node<'a, (), Out = ()>>) {
-        if let Some(_) = self.nodes[pos] {
+        let val = self.wrap(pos, val);
+        let slot = if pos < self.base {
+            match self.recycled.iter_mut().find(|&&mut (p, _)| p == pos) {
+                Some(&mut (_, ref mut slot)) => slot,
+                None => panic!("pos not reserved in Graph::set"),
+            }
+        } else {
+            &mut self.nodes[pos - self.base]
+        };
+        if let Some(_) = *slot {
             panic!("v[pos] != None in Graph::set")
         }
-        self.nodes[pos] = Some(val);
+        *slot = Some(val);
     }
 
     /// Adds a new node to the graph
@@ -45,13 +134,63 @@ impl<'a> Graph<'a> {
     /// It's the same than calling reserve then add.
     /// Returns the id of the added node.
     pub(crate) fn add(&mut self, val: Box<Node<'a, (), Out = ()>>) -> usize {
-        let pos = self.nodes.len();
-        self.nodes.push(Some(val));
+        let pos = self.reserve();
+        self.set(pos, val);
         pos
     }
 
+    /// Pushes a wrapper: until the matching [`pop_wrapper`](struct.Graph.html#method.pop_wrapper),
+    /// all the nodes set in the graph will be wrapped by `w`.
+    ///
+    /// Wrappers are nested: the last pushed wrapper is the innermost one.
+    pub(crate) fn push_wrapper(&mut self, w: Box<NodeWrapper<'a> + 'a>) {
+        self.wrappers.push(w);
+    }
+
+    /// Removes the last pushed wrapper.
+    pub(crate) fn pop_wrapper(&mut self) {
+        self.wrappers.pop();
+    }
+
+    /// Returns a copy of the current wrappers, outermost first.
+    pub(crate) fn clone_wrappers(&self) -> Vec<Box<NodeWrapper<'a> + 'a>> {
+        self.wrappers.iter().map(|w| w.clone_wrapper()).collect()
+    }
+
+    /// Returns the scope of the nodes set in the graph from now on.
+    pub(crate) fn scope(&self) -> Scope<'a> {
+        Scope(self.clone_wrappers())
+    }
+
+    /// Registers a hook built by `hook` on each of the current wrappers: it is run when the
+    /// sub-graph compiled from now on is killed by one of them.
+    pub(crate) fn on_kill<F: Fn() -> Box<KillHook>>(&self, hook: F) {
+        for w in self.wrappers.iter() {
+            w.on_kill(hook());
+        }
+    }
+
+    /// Applies all the current wrappers to a node.
+    fn wrap(&self, pos: usize, val: Box<Node<'a, (), Out = ()>>) -> Box<Node<'a, (), Out = ()>> {
+        let mut node = val;
+        for w in self.wrappers.iter().rev() {
+            node = w.wrap(pos, node);
+        }
+        node
+    }
+
     /// Return the underlying data structure
     pub(crate) fn get(self) -> Vec<Option<Box<Node<'a, (), Out = ()>>>> {
         self.nodes
     }
+
+    /// Returns the nodes placed at recycled ids, then the fresh nodes.
+    pub(crate) fn get_recycling(
+        self,
+    ) -> (
+        Vec<(usize, Option<Box<Node<'a, (), Out = ()>>>)>,
+        Vec<Option<Box<Node<'a, (), Out = ()>>>>,
+    ) {
+        (self.recycled, self.nodes)
+    }
 }
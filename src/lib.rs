@@ -11,6 +11,7 @@ extern crate test;
 extern crate core;
 extern crate crossbeam_deque;
 extern crate crossbeam;
+extern crate num_cpus;
 
 
 #[macro_use]
@@ -157,6 +158,106 @@ mod tests {
         assert_eq!(i.get(), 42);
     }
 
+    #[test]
+    fn with_threads_test() {
+        let i = GCell::new(0);
+        {
+            let mut r = Runtime::with_threads(1, pro!{
+                |_| 42;
+                pause();
+                |v| i.set(v)
+            });
+            r.instant();
+            assert_eq!(i.get(), 0);
+            r.instant();
+        }
+        assert_eq!(i.get(), 42);
+    }
+
+    #[test]
+    fn wait_policy_test() {
+        let mut i = 0;
+        let wait = WaitPolicy { spins: 0, yields: 1, park: true };
+        {
+            let mut r = Runtime::with_wait_policy(2, wait, pro!{
+                |_| ((), ());
+                {
+                    pause() || { pause(); pause() }
+                };
+                |_| i = 42
+            });
+            r.execute();
+        }
+        assert_eq!(i, 42);
+    }
+
+    #[cfg(feature = "par")]
+    #[test]
+    fn default_threads_test() {
+        let r = Runtime::new(pro!{
+            pause()
+        });
+        assert_eq!(r.nb_threads(), default_nb_threads());
+        assert_eq!(r.nb_threads(), ::num_cpus::get());
+    }
+
+    #[test]
+    #[should_panic(expected = "worker panic")]
+    fn worker_panic_test() {
+        let wait = WaitPolicy { spins: 0, yields: 1, park: true };
+        let mut r = Runtime::with_wait_policy(4, wait, pro!{
+            |_| ((), ());
+            {
+                |_: ()| { panic!("worker panic"); } || { pause(); pause() }
+            };
+            |_| ()
+        });
+        r.execute();
+    }
+
+    #[test]
+    fn inline_test() {
+        let i = GCell::new(0);
+        {
+            let mut r = Runtime::inline(pro!{
+                |_| 42;
+                pause();
+                |v| i.set(v)
+            });
+            assert_eq!(r.nb_threads(), 1);
+            r.instant();
+            assert_eq!(i.get(), 0);
+            r.instant();
+        }
+        assert_eq!(i.get(), 42);
+    }
+
+    #[cfg(feature = "par")]
+    #[test]
+    fn seq_par_runtime_test() {
+        let make = |v: i32| pro!{
+            move |_: ()| v;
+            pause();
+            |x: i32| x * 2
+        };
+        let mut seq: SeqRuntime<i32> = SeqRuntime::new(make(21));
+        let mut par: ParRuntime<i32> = Runtime::with_threads(2, make(21));
+        assert_eq!(seq.nb_threads(), 1);
+        assert_eq!(par.nb_threads(), 2);
+        assert_eq!(seq.execute(), Some(42));
+        assert_eq!(par.execute(), Some(42));
+    }
+
+    #[test]
+    fn execute_result_test() {
+        let mut r = rt!{
+            |_| 21;
+            pause();
+            |v: i32| v * 2
+        };
+        assert_eq!(r.execute(), Some(42));
+    }
+
     #[test]
     fn choice_im() {
         let mut i = 0;
@@ -206,6 +307,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn for_while_test() {
+        let mut res = 0;
+        {
+            run!{
+                |_| 0;
+                for (0..5) {
+                    |(i, acc): (i32, i32)| acc + i;
+                    pause()
+                };
+                while (|x: &i32| *x < 100) {
+                    |x: i32| x * 2
+                };
+                |v| res = v
+            }
+        }
+        assert_eq!(res, 160);
+    }
+
+    #[test]
+    fn seq_try_test() {
+        let value = GCell::new(Ok(0));
+        let signal = SignalRuntimeRef::new_mc(2, box |e: i32, v: &mut i32| { *v *= e;});
+        {
+            let mut rt = rt! {
+                |_| Ok(21)?;
+                {pause(); |v: i32| Ok(v * 2)}?;
+                |v: i32| if v > 0 { Err(v) } else { Ok(v) }?;
+                {|_: i32| (); await_s(signal.clone()); |v: i32| Ok(v)};
+                |r| value.set(r)
+            };
+
+            rt.instant();
+            assert_eq!(value.get(), Ok(0));
+            rt.instant();
+            assert_eq!(value.get(), Err(42));
+        }
+    }
+
+    #[test]
+    fn switch_test() {
+        let mut res = 0;
+        {
+            run!{
+                |_| 0;
+                for (0..6) {
+                    match (|(i, acc): (i32, i32)| ((i % 3) as usize, (i, acc)))
+                    {|(i, acc): (i32, i32)| acc + i}
+                    {pause(); |(i, acc): (i32, i32)| acc + 10 * i}
+                    {|(i, acc): (i32, i32)| acc - i}
+                };
+                |v| res = v
+            }
+        }
+        assert_eq!(res, 46);
+    }
+
     #[test]
     fn emit_d_test() {
         let mut value = 0;
@@ -552,6 +710,136 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sc_signal_test() {
+        let mut res = vec![];
+        let signal = SignalRuntimeRef::new_sc(vec![], box |e: i32, v: &mut Vec<i32>| v.push(e));
+        {
+            run! {
+                |_| ((), ());
+                {
+                    {
+                        emit_vs(signal.clone(), 1)
+                    } || {
+                        emit_vs(signal.clone(), 2)
+                    }
+                };
+                |_| ();
+                await_s(signal.clone());
+                |mut v: Vec<i32>| {
+                    v.sort();
+                    res = v;
+                }
+            }
+        }
+        assert_eq!(res, vec![1, 2]);
+    }
+
+    #[test]
+    fn gather_test() {
+        let mut res = (0, None, 0);
+        let sum = SignalRuntimeRef::new_sum();
+        let max = SignalRuntimeRef::new_max();
+        let count = SignalRuntimeRef::new_count();
+        {
+            run! {
+                emit_vs(sum.clone(), 3);
+                emit_vs(sum.clone(), 4);
+                emit_vs(max.clone(), 5);
+                emit_vs(max.clone(), 3);
+                emit_vs(count.clone(), 'a');
+                emit_vs(count.clone(), 'b');
+                emit_vs(count.clone(), 'a');
+                pause();
+                pre_s(sum.clone());
+                pre_s_in(max.clone());
+                pre_s_in(count.clone());
+                |(c, (m, s)): (::std::collections::HashMap<char, usize>, (Option<i32>, i32))| {
+                    res = (s, m, c[&'a']);
+                }
+            }
+        }
+        assert_eq!(res, (7, Some(5), 2));
+    }
+
+    #[test]
+    fn shared_signal_test() {
+        let mut shared = false;
+        let mut sum = 0;
+        let signal = SignalRuntimeRef::new_shared(vec![], box |e: i32, v: &mut Vec<i32>| v.push(e));
+        {
+            run! {
+                |_| ((), ());
+                {
+                    emit_vs(signal.clone(), 1) || emit_vs(signal.clone(), 2)
+                };
+                |_| ((), ());
+                {
+                    await_s(signal.clone()) || await_s(signal.clone())
+                };
+                |(a, b): (Snapshot<Vec<i32>>, Snapshot<Vec<i32>>)| {
+                    shared = Snapshot::ptr_eq(&a, &b);
+                    sum = a.iter().sum();
+                }
+            }
+        }
+        assert!(shared);
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn ordered_signal_test() {
+        // the emissions of the two branches arrive in any order on several threads
+        #[cfg(feature = "par")]
+        let runs = 100;
+        #[cfg(not(feature = "par"))]
+        let runs = 1;
+        for _ in 0..runs {
+            let mut res = vec![];
+            let signal = SignalRuntimeRef::new_ordered(vec![], box |e: i32, v: &mut Vec<i32>| v.push(e));
+            {
+                let mut r = Runtime::with_threads(4, pro! {
+                    |_| ((), ());
+                    {
+                        {
+                            pause();
+                            emit_vs(signal.clone(), 1);
+                            emit_vs(signal.clone(), 2)
+                        } || {
+                            pause();
+                            emit_vs(signal.clone(), 3)
+                        }
+                    };
+                    |_| ();
+                    await_s(signal.clone());
+                    |v: Vec<i32>| {
+                        res = v;
+                    }
+                });
+                r.execute();
+            }
+            // the left branch is compiled first, its main nodes have the smallest ids
+            assert_eq!(res, vec![1, 2, 3]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "single consumer signal read twice")]
+    fn sc_signal_two_readers_test() {
+        let signal = SignalRuntimeRef::new_sc(0, box |e: i32, v: &mut i32| *v += e);
+        run! {
+            |_| ((), ());
+            {
+                emit_vs(signal.clone(), 1) || emit_vs(signal.clone(), 2)
+            };
+            |_| ((), ());
+            {
+                await_s(signal.clone()) || await_s(signal.clone())
+            };
+            |_| ()
+        }
+    }
+
     #[test]
     fn emit_await_immediate() {
         let value = GCell::new(0);
@@ -987,6 +1275,582 @@ mod tests {
         assert_eq!(value, 42);
     }
 
+    #[test]
+    fn abort_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        abort (signal.clone()) {
+                            loop {
+                                |_:()| value.set(value.get() + 1);
+                                pause();
+                                |_| True::<(),i32>(())
+                            }
+                        } {
+                            |_:()| 42
+                        };
+                        |v| value.set(v)
+                    } || {
+                        pause();
+                        emit_vs(signal.clone(), ())
+                    }
+                };
+                |_| ()
+            };
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.execute();
+            assert_eq!(value.get(), 42);
+        }
+        let mut res = 0;
+        {
+            run! {
+                abort (signal.clone()) {
+                    |_:()| 1
+                } {
+                    |_:()| 2
+                };
+                |v| res = v
+            }
+        }
+        assert_eq!(res, 1);
+    }
+
+    #[test]
+    fn abort_loop_test() {
+        let count = GCell::new(0);
+        let kill = SignalRuntimeRef::new_pure();
+        let go = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    loop {
+                        abort (kill.clone()) {
+                            await_s(go.clone());
+                            |_:()| count.set(count.get() + 1)
+                        } {
+                            |_:()| ()
+                        };
+                        |_| True::<(),()>(())
+                    }
+                } || {
+                    pause();
+                    emit_vs(kill.clone(), ());
+                    pause();
+                    pause();
+                    emit_vs(go.clone(), ())
+                };
+                |_| ()
+            };
+            // the body killed while it awaits `go` must not wake up in the next execution
+            rt.instantn(8);
+            assert_eq!(count.get(), 1);
+        }
+    }
+
+    #[test]
+    fn until_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_mc(0, box |e: i32, v: &mut i32| { *v += e;});
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        until (signal.clone()) {
+                            loop {
+                                |_:()| value.set(value.get() + 1);
+                                pause();
+                                |_| True::<(),i32>(())
+                            }
+                        } {
+                            |(v, _s)| v + 100
+                        };
+                        |v| value.set(v)
+                    } || {
+                        pause();
+                        emit_vs(signal.clone(), 5)
+                    }
+                };
+                |_| ()
+            };
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 2);
+            rt.execute();
+            assert_eq!(value.get(), 105);
+        }
+    }
+
+    #[test]
+    fn trap_test() {
+        let value = GCell::new(0);
+        let count = GCell::new(0);
+        let t = Trap::new();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                trap (t.clone()) {
+                    {
+                        loop {
+                            |_:()| count.set(count.get() + 1);
+                            pause();
+                            |_| True::<(),i32>(())
+                        }
+                    } || {
+                        pause();
+                        |_| 42;
+                        exit::<_, ()>(t.clone())
+                    };
+                    |_| 0
+                };
+                |v| value.set(v)
+            };
+            rt.instant();
+            rt.instant();
+            assert_eq!(value.get(), 0);
+            assert_eq!(count.get(), 2);
+            rt.execute();
+            assert_eq!(value.get(), 42);
+            assert_eq!(count.get(), 2);
+        }
+    }
+
+    #[test]
+    fn trap_immediate_test() {
+        let mut res = 0;
+        let t = Trap::new();
+        run! {
+            trap (t.clone()) {
+                |_:()| 1
+            };
+            |v| res = v
+        }
+        assert_eq!(res, 1);
+    }
+
+    #[test]
+    fn local_signal_test() {
+        let mut res = 0;
+        {
+            run!{
+                |_| 0;
+                for (0..3) {
+                    |(_, acc): (i32, i32)| acc;
+                    signal s default 0 gather |e: i32, v: &mut i32| *v += e in {
+                        |(_, acc): (LocalSignal<_>, i32)| ((), acc);
+                        {
+                            {
+                                emit_vs(s.clone(), 10);
+                                await_s(s.clone())
+                            } || {
+                                |acc: i32| acc
+                            }
+                        };
+                        |(v, acc): (i32, i32)| acc + v
+                    }
+                };
+                |v| res = v
+            }
+        }
+        assert_eq!(res, 30);
+    }
+
+    #[test]
+    fn flatten_test() {
+        let value = GCell::new(0);
+        {
+            let p = pro! {
+                |_:()| value.set(value.get() + 1);
+                pause();
+                |_:()| value.get() + 10
+            };
+            let mut rt = rt! {
+                once |_:()| p;
+                flatten();
+                |v| value.set(v)
+            };
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.execute();
+            assert_eq!(value.get(), 11);
+        }
+    }
+
+    #[test]
+    fn flatten_loop_test() {
+        let value = GCell::new(0);
+        {
+            let value = &value;
+            let mut rt = rt! {
+                loop {
+                    move |_:()| pro! {
+                        move |_:()| value.set(value.get() + 1);
+                        pause();
+                        move |_:()| if value.get() < 10 { True(()) } else { False(()) }
+                    };
+                    flatten()
+                };
+                |_| ()
+            };
+            rt.execute();
+        }
+        assert_eq!(value.get(), 10);
+    }
+
+    #[test]
+    fn spawner_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_spawn();
+        {
+            let p1 = pro! {
+                |_:()| value.set(value.get() + 1)
+            };
+            let p2 = pro! {
+                pause();
+                |_:()| value.set(value.get() + 10)
+            };
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        spawner(signal.clone())
+                    } || {
+                        once |_:()| -> BoxedSpawn { box p1 };
+                        emit_s(signal.clone());
+                        once |_:()| -> BoxedSpawn { box p2 };
+                        emit_s(signal.clone())
+                    }
+                };
+                |_| ()
+            };
+            rt.instant();
+            assert_eq!(value.get(), 0);
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 11);
+        }
+    }
+
+    #[test]
+    fn spawner_reuse_test() {
+        let value = GCell::new(0);
+        let n = GCell::new(0);
+        let signal = SignalRuntimeRef::new_spawn();
+        {
+            let value = &value;
+            let n = &n;
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        spawner(signal.clone())
+                    } || loop {
+                        move |_:()| -> BoxedSpawn {
+                            box pro! { move |_:()| value.set(value.get() + 1) }
+                        };
+                        emit_s(signal.clone());
+                        pause();
+                        move |_:()| {
+                            n.set(n.get() + 1);
+                            if n.get() < 5 { True(()) } else { False(()) }
+                        }
+                    }
+                };
+                |_| ()
+            };
+            rt.instantn(10);
+        }
+        assert_eq!(value.get(), 5);
+    }
+
+    #[test]
+    fn every_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        every (signal.clone()) {
+                            |_:()| value.set(value.get() + 1)
+                        }
+                    } || {
+                        pause();
+                        emit_vs(signal.clone(), ());
+                        pause();
+                        pause();
+                        emit_vs(signal.clone(), ())
+                    }
+                };
+                |_| ()
+            };
+            rt.instantn(2);
+            assert_eq!(value.get(), 0);
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 2);
+        }
+    }
+
+    #[test]
+    fn every_restart_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_pure();
+        let go = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        every (signal.clone()) {
+                            await_s(go.clone());
+                            |_:()| value.set(value.get() + 1)
+                        }
+                    } || {
+                        emit_vs(signal.clone(), ());
+                        pause();
+                        pause();
+                        emit_vs(signal.clone(), ());
+                        pause();
+                        pause();
+                        emit_vs(go.clone(), ())
+                    }
+                };
+                |_| ()
+            };
+            rt.instantn(7);
+            assert_eq!(value.get(), 1);
+        }
+    }
+
+    #[test]
+    fn await_s_in_when_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_mc(0, box |e: i32, v: &mut i32| { *v += e;});
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        await_s_in_when(signal.clone(), |v: &i32| *v > 10);
+                        |(v, ())| value.set(v)
+                    } || {
+                        emit_vs(signal.clone(), 3);
+                        pause();
+                        emit_vs(signal.clone(), 5);
+                        pause();
+                        emit_vs(signal.clone(), 12)
+                    }
+                };
+                |_| ()
+            };
+            rt.instantn(3);
+            assert_eq!(value.get(), 0);
+            rt.execute();
+            assert_eq!(value.get(), 12);
+        }
+    }
+
+    #[test]
+    fn race_test() {
+        let value = GCell::new(0);
+        let loser_ended = GCell::new(false);
+        {
+            let slow = pro! {
+                pause();
+                pause();
+                pause();
+                |_:()| { loser_ended.set(true); 1 }
+            };
+            let fast = pro! {
+                pause();
+                |_:()| 2
+            };
+            let mut rt = rt! {
+                |_| ((),());
+                race(slow, fast);
+                |v: node::ChoiceData<i32, i32>| match v {
+                    True(x) => value.set(x),
+                    False(x) => value.set(10 * x),
+                }
+            };
+            rt.instantn(2);
+            assert_eq!(value.get(), 20);
+            rt.execute();
+            assert_eq!(loser_ended.get(), false);
+        }
+    }
+
+    #[test]
+    fn race_loop_test() {
+        let value = GCell::new(0);
+        let go = SignalRuntimeRef::new_pure();
+        {
+            let waiting = pro! {
+                await_s(go.clone());
+                |_:()| value.set(value.get() + 1)
+            };
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    loop {
+                        |_:()| ((),());
+                        race(waiting, pause());
+                        |_| -> node::ChoiceData<(), ()> { True(()) }
+                    }
+                } || {
+                    pause();
+                    pause();
+                    pause();
+                    emit_vs(go.clone(), ())
+                };
+                |_| ()
+            };
+            rt.instantn(6);
+            assert_eq!(value.get(), 1);
+        }
+    }
+
+    #[test]
+    fn big_join_collect_test() {
+        let mut res = vec![];
+        {
+            let processes: Vec<BoxedBranch<i32, i32>> = vec![
+                box pro!(|x: i32| x + 1),
+                box pro!{
+                    pause();
+                    |x: i32| x * 2
+                },
+                box pro!{
+                    pause();
+                    pause();
+                    |x: i32| x - 1
+                },
+            ];
+            run! {
+                |_| vec![1, 10, 100];
+                big_join_collect(processes);
+                |v| res = v
+            }
+        }
+        assert_eq!(res, vec![2, 20, 99]);
+    }
+
+    #[test]
+    fn par_map_test() {
+        let mut res = vec![];
+        {
+            run! {
+                |_| vec![1, 2, 3];
+                par_map(|| pro! {
+                    pause();
+                    |x: i32| x * 2
+                });
+                |v: Vec<i32>| v.into_iter().map(|x| x + 1).collect::<Vec<i32>>();
+                par_map(|| pro! {
+                    pause();
+                    |x: i32| x * 10
+                });
+                |v| res = v
+            }
+        }
+        assert_eq!(res, vec![30, 50, 70]);
+    }
+
+    #[test]
+    fn par_map_threads_test() {
+        let mut res = vec![];
+        {
+            let mut r = Runtime::with_threads(4, pro! {
+                |_| (0..32).collect::<Vec<i32>>();
+                par_map(|| pro! {
+                    pause();
+                    |x: i32| x * 2
+                });
+                |v| res = v
+            });
+            r.execute();
+        }
+        assert_eq!(res, (0..32).map(|x| x * 2).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn signal_expr_test() {
+        let value = GCell::new(0);
+        let s1 = SignalRuntimeRef::new_pure();
+        let s2 = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    {
+                        present (s1.clone() && !s2.clone()) {
+                            |_:()| value.set(1)
+                        } {
+                            |_:()| value.set(2)
+                        };
+                        await_any(vec![s1.clone(), s2.clone()]);
+                        |_:()| value.set(value.get() * 10)
+                    } || {
+                        emit_vs(s1.clone(), ());
+                        pause();
+                        pause();
+                        emit_vs(s2.clone(), ())
+                    }
+                };
+                |_| ()
+            };
+            rt.instantn(2);
+            assert_eq!(value.get(), 1);
+            rt.execute();
+            assert_eq!(value.get(), 10);
+        }
+    }
+
+    #[test]
+    fn when_test() {
+        let value = GCell::new(0);
+        let signal = SignalRuntimeRef::new_pure();
+        {
+            let mut rt = rt! {
+                |_| ((),());
+                {
+                    when (signal.clone()) {
+                        loop {
+                            |_:()| value.set(value.get() + 1);
+                            pause();
+                            |_| True::<(),()>(())
+                        }
+                    } || {
+                        emit_vs(signal.clone(), ());
+                        pause();
+                        pause();
+                        emit_vs(signal.clone(), ())
+                    }
+                };
+                |_| ()
+            };
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 1);
+            rt.instant();
+            assert_eq!(value.get(), 2);
+        }
+    }
+
     #[bench]
     fn bench_emitd_pause(bencher: &mut Bencher) {
         let signal = SignalRuntimeRef::new_pure();
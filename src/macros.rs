@@ -18,12 +18,34 @@
 //! from the basic expression we build the grammar with :
 //!
 //! * `P ; Q`: transformed to `P.seq(Q)`
+//! * `P ? ; Q`: transformed to `P.seq_try(Q)`, Q is skipped if P returns `Err`. A closure
+//!   ending with the `?` operator must thus be put in `()` or `{}`
 //! * `P || Q`: transformed to `P.join(Q)`
 //! * `choice {P}{Q}`: transformed to `P.choice(Q)`
 //! * `present {P}{Q}`: transformed to `P.present(Q)`
+//! * `present (e) {P}{Q}`: transformed to `P.present_expr(e,Q)`, where `e` is built from
+//!   signals with `&&`, `||`, `!` and parentheses, each signal `s` becoming `sig_expr(s)`
 //! * `loop {P}`: transformed to `P.ploop()`
+//! * `abort (s) {P}{Q}`: transformed to `P.abort(s,Q)`
+//! * `when (s) {P}`: transformed to `P.when(s)`
+//! * `until (s) {P}{Q}`: transformed to `P.until(s,Q)`, Q takes the value of s and s
+//! * `every (s) {P}`: transformed to `P.every(s)`
+//! * `loop_each (s) {P}`: transformed to `P.loop_each(s)`
+//! * `trap (t) {P}`: transformed to `P.trap(t)`, P can be exited with `exit(t)`
+//! * `signal s in {P}`: declares a local pure signal `s`, transformed to
+//!   `local_signal(s.clone(), || SignalRuntimeRef::new_pure(), P)` where `s` is a new `LocalSignal`.
+//!   P takes `(s, v)` where `v` is the input value
+//! * `signal s default d gather g in {P}`: same with `SignalRuntimeRef::new_mc(d, box g)`,
+//!   `d` and `g` are evaluated each time the signal is created.
+//!   Only these two kinds of signal can be declared this way: the other kinds (single
+//!   consumer, shared, ordered, spawn, ...) need a direct call to `local_signal`
+//!   with their constructor
+//! * `for (iter) {P}`: transformed to `pfor(iter,P)`, P takes the item and the loop value
+//! * `while (cond) {P}`: transformed to `pwhile(cond,P)`
+//! * `match (sel) {P0} {P1} ...`: transformed to `pswitch(sel, branches)` where branches
+//!   is the `Vec` of the `boxed_branch(Pi)`
 //!
-//! `once`, `val`, `choice` and `present` are considered as keyword in the macro `pro!` and thus
+//! `once`, `val`, `choice`, `present`, `abort`, `when`, `until`, `every`, `loop_each`, `trap`, `signal`, `for`, `while` and `match` are considered as keyword in the macro `pro!` and thus
 //! should not be used if not encapsulated in `()`, `[]` or `{}`.
 //!
 //! A `;` with nothing behind add a PNothing and thus force the output type to be ();
@@ -146,3 +146,196 @@ where
         }
     }
 }
+
+/// Node that wraps its input in `True`, to continue a `LoopIm`
+///
+/// Signature : `I -> ChoiceData<I,O>`
+pub struct NContinue<Out>(PhantomData<Out>);
+
+pub fn ncontinue<Out>() -> NContinue<Out> {
+    NContinue(PhantomData)
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> Node<'a, In> for NContinue<Out> {
+    type Out = ChoiceData<In, Out>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        True(val)
+    }
+}
+
+/// Node that wraps its input in `False`, to exit a `LoopIm`
+///
+/// Signature : `O -> ChoiceData<I,O>`
+pub struct NBreak<In>(PhantomData<In>);
+
+pub fn nbreak<In>() -> NBreak<In> {
+    NBreak(PhantomData)
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> Node<'a, Out> for NBreak<In> {
+    type Out = ChoiceData<In, Out>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: Out) -> Self::Out {
+        False(val)
+    }
+}
+
+
+//  _____
+// |  ___|__  _ __
+// | |_ / _ \| '__|
+// |  _| (_) | |
+// |_|  \___/|_|
+
+/// Node that starts a new iteration over a clone of `iter`, for `NForStep`.
+///
+/// Signature : `I -> I`
+pub struct NForInit<I, It> {
+    pub(crate) iter: I,
+    pub(crate) it: RCell<It>,
+}
+
+impl<'a, I: Val<'a>, It: Val<'a>, In: Val<'a>> Node<'a, In> for NForInit<I, It>
+where
+    I: IntoIterator<IntoIter = It> + Clone,
+{
+    type Out = In;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        self.it.set(self.iter.clone().into_iter());
+        val
+    }
+}
+
+/// Node that gives the next item of the iteration with the loop value,
+/// or exits the loop when the iteration is over. See `process::PFor`
+///
+/// Signature : `I -> ChoiceData<(Item,I),I>`
+pub struct NForStep<It> {
+    pub(crate) it: RCell<It>,
+}
+
+impl<'a, It: Val<'a>, In: Val<'a>> Node<'a, In> for NForStep<It>
+where
+    It: Iterator,
+    It::Item: Val<'a>,
+{
+    type Out = ChoiceData<(It::Item, In), In>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        let mut it = self.it.get();
+        match it.next() {
+            Some(item) => {
+                self.it.set(it);
+                True((item, val))
+            }
+            None => False(val),
+        }
+    }
+}
+
+
+// __        ___     _ _
+// \ \      / / |__ (_) | ___
+//  \ \ /\ / /| '_ \| | |/ _ \
+//   \ V  V / | | | | | |  __/
+//    \_/\_/  |_| |_|_|_|\___|
+
+/// Node that continues the loop while the condition holds on the loop value.
+/// See `process::PWhile`
+///
+/// Signature : `I -> ChoiceData<I,I>`
+pub struct NWhileStep<F>(pub F);
+
+impl<'a, F: Val<'a>, In: Val<'a>> Node<'a, In> for NWhileStep<F>
+where
+    F: FnMut(&In) -> bool,
+{
+    type Out = ChoiceData<In, In>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        if (self.0)(&val) {
+            True(val)
+        } else {
+            False(val)
+        }
+    }
+}
+
+
+//  ____          _ _       _
+// / ___|_      _(_) |_ ___| |__
+// \___ \ \ /\ / / | __/ __| '_ \
+//  ___) \ V  V /| | || (__| | | |
+// |____/ \_/\_/ |_|\__\___|_| |_|
+
+/// Node that calls `selector` on its input to get a branch index and a payload,
+/// and then jumps to the branch with the payload. See `process::PSwitch`
+///
+/// It panics if the index is not the one of a branch.
+///
+/// Signature : `I -> ()`
+pub struct NSwitch<F, T> {
+    pub(crate) selector: F,
+    pub(crate) rc: RCell<T>,
+    pub(crate) dests: Vec<usize>,
+}
+
+impl<'a, F: Val<'a>, T: Val<'a>, In: Val<'a>> Node<'a, In> for NSwitch<F, T>
+where
+    F: FnMut(In) -> (usize, T),
+{
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        let (ind, payload) = (self.selector)(val);
+        assert!(ind < self.dests.len(), "switch: no branch {}", ind);
+        self.rc.set(payload);
+        sub_runtime.add_current(self.dests[ind]);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> Switch: {} in {}", ind, tname::<T>(), self.rc.get_ind(cfgd));
+        for d in &self.dests {
+            cfgd.add_arrow((ind, *d));
+        }
+    }
+}
+
+
+//  _____
+// |_   _| __ _   _
+//   | || '__| | | |
+//   | || |  | |_| |
+//   |_||_|   \__, |
+//            |___/
+
+/// Node that maps `Ok` to `True` and `Err` to `False`. See `process::SeqTry`
+///
+/// Signature : `Result<T,E> -> ChoiceData<T,E>`
+pub struct NTry;
+
+pub fn ntry() -> NTry {
+    NTry
+}
+
+impl<'a, T: Val<'a>, E: Val<'a>> Node<'a, Result<T, E>> for NTry {
+    type Out = ChoiceData<T, E>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: Result<T, E>) -> Self::Out {
+        match val {
+            Ok(t) => True(t),
+            Err(e) => False(e),
+        }
+    }
+}
+
+/// Node that wraps its input in `Err`, to propagate an error. See `process::SeqTry`
+///
+/// Signature : `E -> Result<T,E>`
+pub struct NErr<T>(PhantomData<T>);
+
+pub fn nerr<T>() -> NErr<T> {
+    NErr(PhantomData)
+}
+
+impl<'a, T: Val<'a>, E: Val<'a>> Node<'a, E> for NErr<T> {
+    type Out = Result<T, E>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: E) -> Self::Out {
+        Err(val)
+    }
+}
@@ -0,0 +1,252 @@
+use engine::*;
+use graph::NodeWrapper;
+use process::{Spawnable, BoxedSpawn, Branch};
+use signal::*;
+use super::*;
+
+/// Compiles `p` in the running runtime, under the given wrappers and then `guard`, and returns
+/// its start id. The ids of `ids` are reused first, see `SubRuntime::add_graph_recycling`.
+///
+/// `guard` must have been entered with a new tag before, so that it drops the tasks of the
+/// process previously compiled at these ids.
+fn compile_at_runtime<'a, P: ?Sized>(
+    sub_runtime: &mut SubRuntime<'a>,
+    wrappers: &[Box<NodeWrapper<'a> + 'a>],
+    guard: &RcPreempt,
+    ids: &mut Vec<usize>,
+    p: Box<P>,
+    end: Box<Node<'a, P::Out, Out = ()>>,
+) -> usize
+where
+    P: Spawnable<'a>,
+{
+    sub_runtime.add_graph_recycling(ids, |g| {
+        for w in wrappers {
+            g.push_wrapper(w.clone_wrapper());
+        }
+        g.push_wrapper(box guard.clone());
+        p.compile_spawn(g, end)
+    })
+}
+
+//  _____ _       _   _
+// |  ___| | __ _| |_| |_ ___ _ __
+// | |_  | |/ _` | __| __/ _ \ '_ \
+// |  _| | | (_| | |_| ||  __/ | | |
+// |_|   |_|\__,_|\__|\__\___|_| |_|
+
+/// Node compiling the process it receives as input in the running runtime, and starting it
+/// in the current instant. The output of the process is stored in `rc` and then `dest` is
+/// called (see `process::PFlatten`).
+///
+/// The compiled nodes are wrapped by the wrappers that were active when this node was compiled,
+/// so the spawned process is controlled by the same preemption constructs as this node,
+/// and then by `guard`, which is entered with a new tag at each call.
+///
+/// When this node is called again, the process it started before is over: it terminated or it
+/// was killed. The new process is then compiled at the ids of the previous one, which are kept
+/// in `ids`, and `guard` drops the tasks of the previous process that could still be pending.
+///
+/// Signature : `P -> ()`
+pub struct NFlatten<'a, Out> {
+    pub(crate) rc: RCell<Out>,
+    pub(crate) dest: usize,
+    pub(crate) guard: RcPreempt,
+    pub(crate) ids: Vec<usize>,
+    pub(crate) wrappers: Vec<Box<NodeWrapper<'a> + 'a>>,
+}
+
+impl<'a, P, Out: Val<'a>> Node<'a, P> for NFlatten<'a, Out>
+where
+    P: Spawnable<'a, Out = Out>,
+{
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, p: P) -> Self::Out {
+        let tag = sub_runtime.new_tag();
+        self.guard.enter(tag);
+        let end = box node!(store(self.rc.clone()) >> njump(self.dest));
+        let start = compile_at_runtime(
+            sub_runtime,
+            &self.wrappers,
+            &self.guard,
+            &mut self.ids,
+            box p,
+            end,
+        );
+        sub_runtime.add_current(start);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> Flatten", ind);
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+
+//  ____
+// / ___| _ __   __ ___      ___ __
+// \___ \| '_ \ / _` \ \ /\ / / '_ \
+//  ___) | |_) | (_| |\ V  V /| | | |
+// |____/| .__/ \__,_| \_/\_/ |_| |_|
+//       |_|
+
+/// Main node starting all the processes emitted on `signal` during the last instant,
+/// and then awaiting the signal again to be called at the instant following its next emission
+/// (see `process::PSpawner`). `id` must be the id of this node.
+///
+/// Each process is compiled in a slot of `slots`. A slot is reused by a new process once
+/// its process terminated or was killed, so the runtime only grows with the number of
+/// processes running at the same time.
+///
+/// Signature : `() -> ()`
+pub struct NSpawn<'a, S> {
+    pub(crate) signal: S,
+    pub(crate) id: usize,
+    pub(crate) slots: Vec<SpawnSlot>,
+    pub(crate) wrappers: Vec<Box<NodeWrapper<'a> + 'a>>,
+}
+
+/// The nodes of a process started by `NSpawn`.
+pub struct SpawnSlot {
+    /// Set when the process terminates.
+    done: RCell<()>,
+    /// Guard wrapping the nodes of the process, entered with a new tag at each use of the slot.
+    guard: RcPreempt,
+    /// The tag with which the process was started.
+    tag: usize,
+    /// Whether the slot can be reused.
+    free: bool,
+    /// The ids of the nodes of the slot.
+    ids: Vec<usize>,
+}
+
+impl<'a, S> NSpawn<'a, S> {
+    /// Frees the slots whose process terminated, or was killed by a preemption of the spawner.
+    fn free_slots(&mut self) {
+        for slot in &mut self.slots {
+            let alive = self.wrappers.iter().all(|w| w.alive(slot.tag));
+            if !slot.free && (slot.done.try_get().is_some() || !alive) {
+                slot.free = true;
+            }
+        }
+    }
+
+    /// Returns the index of a free slot, adding one if needed.
+    fn free_slot(&mut self) -> usize {
+        match self.slots.iter().position(|slot| slot.free) {
+            Some(i) => i,
+            None => {
+                self.slots.push(SpawnSlot {
+                    done: RCell::new(),
+                    guard: RcPreempt::new(),
+                    tag: 0,
+                    free: true,
+                    ids: vec![],
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+}
+
+impl<'a, S> Node<'a, ()> for NSpawn<'a, S>
+where
+    S: Signal<'a, V = Vec<BoxedSpawn<'a>>>,
+{
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        let instant = sub_runtime.get_current_instant();
+        self.free_slots();
+        for p in self.signal.get_pre_value(instant) {
+            let i = self.free_slot();
+            let tag = sub_runtime.new_tag();
+            let slot = &mut self.slots[i];
+            slot.done = RCell::new();
+            slot.guard.enter(tag);
+            slot.tag = tag;
+            slot.free = false;
+            let end = box store(slot.done.clone());
+            let start =
+                compile_at_runtime(sub_runtime, &self.wrappers, &slot.guard, &mut slot.ids, p, end);
+            sub_runtime.add_current(start);
+        }
+        self.signal.await(sub_runtime, self.id);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> Spawn", ind);
+        cfgd.add_arrow((ind, self.id));
+    }
+}
+
+
+//  ____            __  __
+// |  _ \ __ _ _ __|  \/  | __ _ _ __
+// | |_) / _` | '__| |\/| |/ _` | '_ \
+// |  __/ (_| | |  | |  | | (_| | |_) |
+// |_|   \__,_|_|  |_|  |_|\__,_| .__/
+//                              |_|
+
+/// Node starting one process per value of its input vector, the process i taking the i-th
+/// value. When all the processes are finished, their results are in `rc` and `dest` is called
+/// (see `process::PParMap`).
+///
+/// The processes are built by `factory` and compiled in the running runtime the first time a
+/// vector of their size is received. They are then reused by the following calls.
+///
+/// Signature : `Vec<T> -> ()`
+pub struct NParMap<'a, T, U, F> {
+    pub(crate) factory: F,
+    pub(crate) cells: Vec<RCell<T>>,
+    pub(crate) starts: Vec<usize>,
+    pub(crate) rc: Rccp<U>,
+    pub(crate) dest: usize,
+    pub(crate) wrappers: Vec<Box<NodeWrapper<'a> + 'a>>,
+}
+
+impl<'a, T: Val<'a>, U: Val<'a>, F: Val<'a>, P> Node<'a, Vec<T>> for NParMap<'a, T, U, F>
+where
+    F: FnMut() -> P,
+    P: Branch<'a, T, U>,
+{
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, vals: Vec<T>) -> Self::Out {
+        self.rc.reset(vals.len());
+        if vals.is_empty() {
+            sub_runtime.add_current(self.dest);
+        }
+        if self.starts.len() < vals.len() {
+            // all the missing processes are compiled in a single graph
+            let first = self.starts.len();
+            let dest = self.dest;
+            let factory = &mut self.factory;
+            let wrappers = &self.wrappers;
+            let rc = &self.rc;
+            let cells = &mut self.cells;
+            let starts = &mut self.starts;
+            sub_runtime.add_graph(|g| {
+                for w in wrappers {
+                    g.push_wrapper(w.clone_wrapper());
+                }
+                for i in first..vals.len() {
+                    let p = box (*factory)();
+                    let end = box collect(rc.clone(), i, dest);
+                    let rcin = RCell::new();
+                    let pni = p.compile_branch(g, end);
+                    starts.push(g.add(box node!(load(rcin.clone()) >> pni)));
+                    cells.push(rcin);
+                }
+                starts[first]
+            });
+        }
+        for ((cell, start), val) in self.cells.iter().zip(&self.starts).zip(vals) {
+            cell.set(val);
+            sub_runtime.add_current_spread(*start);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> ParMap in {}", ind, self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
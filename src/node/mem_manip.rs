@@ -26,6 +26,10 @@ mod content {
         pub fn get(&self) -> T {
             self.0.take().unwrap()
         }
+        /// Takes the value, if it was set.
+        pub fn try_get(&self) -> Option<T> {
+            self.0.take()
+        }
         pub fn get_copy(&self) -> T
         where
             T: Copy,
@@ -63,6 +67,10 @@ mod content {
         pub fn get(&self) -> T {
             (*self.0.lock().unwrap()).take().unwrap()
         }
+        /// Takes the value, if it was set.
+        pub fn try_get(&self) -> Option<T> {
+            (*self.0.lock().unwrap()).take()
+        }
         pub fn get_copy(&self) -> T
         where
             T: Copy,
@@ -107,6 +115,11 @@ mod content {
             let r :&mut Option<T> = unsafe {(self.0).0.get().as_mut().unwrap()};
             take(r).unwrap()
         }
+        /// Takes the value, if it was set.
+        pub fn try_get(&self) -> Option<T> {
+            let r :&mut Option<T> = unsafe {(self.0).0.get().as_mut().unwrap()};
+            take(r)
+        }
         pub fn get_copy(&self) -> T
             where
             T: Copy,
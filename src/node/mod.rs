@@ -37,6 +37,12 @@ pub use self::par::*;
 mod signal;
 #[doc(hidden)] // for private doc remove for public doc
 pub use self::signal::*;
+mod preempt;
+#[doc(hidden)] // for private doc remove for public doc
+pub use self::preempt::*;
+mod dynamic;
+#[doc(hidden)] // for private doc remove for public doc
+pub use self::dynamic::*;
 
 
 //  _   _           _
@@ -90,6 +96,20 @@ pub trait Node<'a, In: Val<'a>>: Val<'a> {
     }
 }
 
+/// A boxed node is a node, this allows to use nodes whose type is only known at runtime.
+impl<'a, In: Val<'a>, N: ?Sized> Node<'a, In> for Box<N>
+where
+    N: Node<'a, In>,
+{
+    type Out = N::Out;
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        (**self).call(sub_runtime, val)
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        (**self).printDot(cfgd)
+    }
+}
+
 //  _   _       _   _     _
 // | \ | | ___ | |_| |__ (_)_ __   __ _
 // |  \| |/ _ \| __| '_ \| | '_ \ / _` |
@@ -248,16 +268,24 @@ where
 // | |___| | | | (_| |
 // |_____|_| |_|\__,_|
 
-/// Node that signal to the runtime that the whole process has ended
+/// Node that signal to the runtime that the whole process has ended,
+/// and stores the value outputted by the process for the runtime.
 ///
 /// Only used by `compile_to_graph` functions.
 ///
-/// Signature : `() -> ()`
-pub struct NEnd{}
-impl<'a> Node<'a, ()> for NEnd
+/// Signature : `T -> ()`
+pub struct NEnd<T: OptSend>(RCell<T>);
+
+/// Builds a `NEnd`
+pub fn nend<T: OptSend>(result: RCell<T>) -> NEnd<T> {
+    NEnd(result)
+}
+
+impl<'a, T: Val<'a>> Node<'a, T> for NEnd<T>
 {
     type Out = ();
-    fn call(&mut self, sub: &mut SubRuntime<'a>, _: ()) -> () {
+    fn call(&mut self, sub: &mut SubRuntime<'a>, val: T) -> () {
+        self.0.set(val);
         sub.end();
     }
 }
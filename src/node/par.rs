@@ -135,6 +135,71 @@ impl<T1,T2> JoinPoint<T1,T2> {
     pub fn get(self : Self) -> (T1, T2) {
         (self.o1.unwrap(), self.o2.unwrap())
     }
+    /// Cancels the values set by the branches, when a new join starts.
+    ///
+    /// The previous join either completed, or was killed with one branch already set.
+    pub fn cancel(&mut self) {
+        self.o1 = None;
+        self.o2 = None;
+    }
+}
+
+/// A structure for saving the value of the first branch of a `process::Race` to finish.
+///
+/// The other branch is cancelled at the end of the instant when the race is won:
+/// the nodes of both branches are guarded by the `Rcrp` pointing to this structure.
+/// Each race takes a new tag, so the tasks of a previous race on the same structure
+/// (see `engine::Task`) are cancelled too.
+pub struct RacePoint<T1, T2> {
+    res: Option<ChoiceData<T1, T2>>,
+    /// The instant when a branch finished first
+    won: Option<usize>,
+    /// The tag with which the current race started
+    start: usize,
+}
+
+impl<T1, T2> Default for RacePoint<T1, T2> {
+    fn default() -> Self {
+        RacePoint { res: None, won: None, start: 0 }
+    }
+}
+
+impl<T1, T2> RacePoint<T1, T2> {
+    /// Starts a new race with the tag `tag`.
+    pub fn start(&mut self, tag: usize) {
+        self.res = None;
+        self.won = None;
+        self.start = tag;
+    }
+    /// Returns true if the first branch is the first one to finish.
+    pub fn set1(&mut self, t: T1, current_instant: usize) -> bool {
+        if self.won.is_some() {
+            return false;
+        }
+        self.won = Some(current_instant);
+        self.res = Some(ChoiceData::True(t));
+        true
+    }
+    /// Returns true if the second branch is the first one to finish.
+    pub fn set2(&mut self, t: T2, current_instant: usize) -> bool {
+        if self.won.is_some() {
+            return false;
+        }
+        self.won = Some(current_instant);
+        self.res = Some(ChoiceData::False(t));
+        true
+    }
+    /// Returns whether a task of tag `tag` of the current race may still run at `current_instant`.
+    pub fn running(&self, current_instant: usize, tag: usize) -> bool {
+        tag >= self.start && self.won.map_or(true, |instant| instant == current_instant)
+    }
+    /// Returns whether a task of tag `tag` may still run in a following instant.
+    pub fn alive(&self, tag: usize) -> bool {
+        tag >= self.start && self.won.is_none()
+    }
+    pub fn get(&mut self) -> ChoiceData<T1, T2> {
+        self.res.take().unwrap()
+    }
 }
 
 #[cfg(not(feature = "par"))]
@@ -159,6 +224,9 @@ mod content {
         pub fn set2(&self, t: T2) -> bool {
             self.0.borrow_mut().set2(t)
         }
+        pub fn cancel(&self) {
+            self.0.borrow_mut().cancel()
+        }
         pub fn get(&self) -> (T1, T2) {
             take(&mut *self.0.borrow_mut()).get()
         }
@@ -167,6 +235,42 @@ mod content {
         }
     }
 
+    /// struct sharing and pointing to a `RacePoint`
+    pub struct Rcrp<T1, T2>(Rc<RefCell<RacePoint<T1, T2>>>);
+
+    impl<T1,T2> Clone for Rcrp<T1,T2>{
+        fn clone(&self) -> Self{
+            Rcrp(self.0.clone())
+        }
+    }
+
+    impl<T1, T2> Rcrp<T1, T2> {
+        pub fn new() -> Self {
+            Rcrp(Rc::new(RefCell::new(RacePoint::default())))
+        }
+        pub fn start(&self, tag: usize) {
+            self.0.borrow_mut().start(tag)
+        }
+        pub fn set1(&self, t: T1, current_instant: usize) -> bool {
+            self.0.borrow_mut().set1(t, current_instant)
+        }
+        pub fn set2(&self, t: T2, current_instant: usize) -> bool {
+            self.0.borrow_mut().set2(t, current_instant)
+        }
+        pub fn running(&self, current_instant: usize, tag: usize) -> bool {
+            self.0.borrow().running(current_instant, tag)
+        }
+        pub fn alive(&self, tag: usize) -> bool {
+            self.0.borrow().alive(tag)
+        }
+        pub fn get(&self) -> ChoiceData<T1, T2> {
+            self.0.borrow_mut().get()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Rc::into_raw(self.0.clone()))
+        }
+    }
+
 }
 
 #[cfg(feature = "par")]
@@ -193,6 +297,9 @@ mod content {
         pub fn set2(&self, t: T2) -> bool {
             self.0.lock().unwrap().set2(t)
         }
+        pub fn cancel(&self) {
+            self.0.lock().unwrap().cancel()
+        }
         pub fn get(&self) -> (T1, T2) {
             take(&mut *self.0.lock().unwrap()).get()
         }
@@ -201,6 +308,41 @@ mod content {
         }
     }
 
+    pub struct Rcrp<T1, T2>(Arc<Mutex<RacePoint<T1, T2>>>);
+
+    impl<T1: Send,T2: Send> Clone for Rcrp<T1,T2>{
+        fn clone(&self) -> Self{
+            Rcrp(self.0.clone())
+        }
+    }
+
+    impl<T1: Send, T2: Send> Rcrp<T1, T2> {
+        pub fn new() -> Self {
+            Rcrp(Arc::new(Mutex::new(RacePoint::default())))
+        }
+        pub fn start(&self, tag: usize) {
+            self.0.lock().unwrap().start(tag)
+        }
+        pub fn set1(&self, t: T1, current_instant: usize) -> bool {
+            self.0.lock().unwrap().set1(t, current_instant)
+        }
+        pub fn set2(&self, t: T2, current_instant: usize) -> bool {
+            self.0.lock().unwrap().set2(t, current_instant)
+        }
+        pub fn running(&self, current_instant: usize, tag: usize) -> bool {
+            self.0.lock().unwrap().running(current_instant, tag)
+        }
+        pub fn alive(&self, tag: usize) -> bool {
+            self.0.lock().unwrap().alive(tag)
+        }
+        pub fn get(&self) -> ChoiceData<T1, T2> {
+            self.0.lock().unwrap().get()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Arc::into_raw(self.0.clone()))
+        }
+    }
+
 
 }
 
@@ -210,6 +352,29 @@ pub use self::content::*;
 
 
 
+/// Node that starts a new join on an Rcjp, cancelling the value left by the branch
+/// of a killed join. See `process::Par`
+///
+/// Signature : `T -> T`
+pub struct NJoinStart<T1, T2> {
+    rc: Rcjp<T1, T2>,
+}
+
+pub fn join_start<T1, T2>(rc: Rcjp<T1, T2>) -> NJoinStart<T1, T2> {
+    NJoinStart { rc }
+}
+
+impl<'a, T1: Val<'a>, T2: Val<'a>, In: Val<'a>> Node<'a, In> for NJoinStart<T1, T2> {
+    type Out = In;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        self.rc.cancel();
+        val
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("JoinStart in {}", self.rc.get_ind(cfgd))
+    }
+}
+
 /// Node that will set the first value of an Rcjp and then jump to dest if the
 /// second is already set. See `process::Par`
 ///
@@ -306,6 +471,135 @@ impl<'a, T1: Val<'a>, T2: Val<'a>> Node<'a, ()> for NMerge<T1, T2> {
 }
 
 
+//  ____
+// |  _ \ __ _  ___ ___
+// | |_) / _` |/ __/ _ \
+// |  _ < (_| | (_|  __/
+// |_| \_\__,_|\___\___|
+
+impl<'a, T1: Val<'a>, T2: Val<'a>> Guard<'a> for Rcrp<T1, T2> {
+    fn check(&self, sub_runtime: &mut SubRuntime<'a>, _: usize) -> bool {
+        let tag = sub_runtime.get_current_tag();
+        self.running(sub_runtime.get_current_instant(), tag)
+    }
+    fn alive(&self, tag: usize) -> bool {
+        Rcrp::alive(self, tag)
+    }
+}
+
+/// Node that starts a new race on an Rcrp, with a new tag. See `process::Race`
+///
+/// Signature : `T -> T`
+pub struct NRaceStart<T1, T2> {
+    rc: Rcrp<T1, T2>,
+}
+
+pub fn race_start<T1, T2>(rc: Rcrp<T1, T2>) -> NRaceStart<T1, T2> {
+    NRaceStart { rc }
+}
+
+impl<'a, T1: Val<'a>, T2: Val<'a>, In: Val<'a>> Node<'a, In> for NRaceStart<T1, T2> {
+    type Out = In;
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        let tag = sub_runtime.new_tag();
+        self.rc.start(tag);
+        val
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("RaceStart in {}", self.rc.get_ind(cfgd))
+    }
+}
+
+/// Node that will set the first value of an Rcrp and then jump to dest if the
+/// first branch won the race. See `process::Race`
+///
+/// Signature : `T1 -> ()`
+pub struct NRaceSet1<T1, T2> {
+    rc: Rcrp<T1, T2>,
+    dest: usize,
+}
+
+pub fn race1<T1, T2>(rc: Rcrp<T1, T2>, dest: usize) -> NRaceSet1<T1, T2> {
+    NRaceSet1 { rc, dest }
+}
+
+impl<'a, T1: Val<'a>, T2: Val<'a>> Node<'a, T1> for NRaceSet1<T1, T2> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: T1) {
+        if self.rc.set1(val, sub_runtime.get_current_instant()) {
+            sub_runtime.add_current(self.dest);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!(
+            "<f{}> Race1: {} in {}",
+            ind,
+            tname::<T1>(),
+            self.rc.get_ind(cfgd)
+        );
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+/// Node that will set the second value of an Rcrp and then jump to dest if the
+/// second branch won the race. See `process::Race`
+///
+/// Signature : `T2 -> ()`
+pub struct NRaceSet2<T1, T2> {
+    rc: Rcrp<T1, T2>,
+    dest: usize,
+}
+
+pub fn race2<T1, T2>(rc: Rcrp<T1, T2>, dest: usize) -> NRaceSet2<T1, T2> {
+    NRaceSet2 { rc, dest }
+}
+
+impl<'a, T1: Val<'a>, T2: Val<'a>> Node<'a, T2> for NRaceSet2<T1, T2> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: T2) {
+        if self.rc.set2(val, sub_runtime.get_current_instant()) {
+            sub_runtime.add_current(self.dest);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!(
+            "<f{}> Race2: {} in {}",
+            ind,
+            tname::<T2>(),
+            self.rc.get_ind(cfgd)
+        );
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+/// Node that, when called, extract the value of the winner of the race set in Rcrp
+///
+/// Signature : `() -> ChoiceData<T1,T2>`
+pub struct NRaceGet<T1, T2> {
+    rc: Rcrp<T1, T2>,
+}
+
+pub fn race_get<T1, T2>(rc: Rcrp<T1, T2>) -> NRaceGet<T1, T2> {
+    NRaceGet { rc }
+}
+
+impl<'a, T1: Val<'a>, T2: Val<'a>> Node<'a, ()> for NRaceGet<T1, T2> {
+    type Out = ChoiceData<T1, T2>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.get()
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!(
+            "RaceGet: {} in {}",
+            tname::<ChoiceData<T1, T2>>(),
+            self.rc.get_ind(cfgd)
+        )
+    }
+}
+
+
 //  ____  _
 // | __ )(_) __ _
 // |  _ \| |/ _` |
@@ -314,22 +608,64 @@ impl<'a, T1: Val<'a>, T2: Val<'a>> Node<'a, ()> for NMerge<T1, T2> {
 //          |___/
 
 
-/// Node that, when called, dump all the ids it owns, in the scheduler for current instant.
+/// Node that, when called, resets its join point and dump all the ids it owns,
+/// in the scheduler for current instant.
 ///
 /// Signature : `() -> (T1,T2)`
 pub struct NBigPar {
+    pub(crate) rc: Rcbjp,
     pub(crate) dests: Vec<usize>,
 }
 
 impl<'a> Node<'a, ()> for NBigPar {
     type Out = ();
     fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.reset();
         for d in &self.dests {
             sub_runtime.add_current(*d);
         }
     }
 }
 
+/// A structure for saving the values of the processes of a `process::BigParCollect`
+/// while waiting for all of them to finish.
+pub struct CollectPoint<T> {
+    vals: Vec<Option<T>>,
+    nb: usize,
+}
+
+impl<T> CollectPoint<T> {
+    pub fn new(total: usize) -> Self {
+        let mut vals = Vec::with_capacity(total);
+        for _ in 0..total {
+            vals.push(None);
+        }
+        CollectPoint { vals, nb: 0 }
+    }
+    /// Starts a new collection of `total` values.
+    pub fn reset(&mut self, total: usize) {
+        self.vals.clear();
+        for _ in 0..total {
+            self.vals.push(None);
+        }
+        self.nb = 0;
+    }
+    /// Sets the value of the process `ind`, returns true if it is the last one.
+    pub fn set(&mut self, ind: usize, t: T) -> bool {
+        self.vals[ind] = Some(t);
+        self.nb += 1;
+        if self.nb == self.vals.len() {
+            self.nb = 0;
+            true
+        } else {
+            false
+        }
+    }
+    pub fn get(&mut self) -> Vec<T> {
+        self.vals.iter_mut().map(|v| v.take().unwrap()).collect()
+    }
+}
+
 #[cfg(not(feature = "par"))]
 mod content2 {
     use super::*;
@@ -357,6 +693,37 @@ mod content2 {
             }
             return None;
         }
+        /// Starts a new join, forgetting the processes of a killed join.
+        pub fn reset(&self) {
+            self.nb.set(0);
+        }
+    }
+
+    /// struct sharing and pointing to a `CollectPoint`
+    pub struct Rccp<T>(Rc<RefCell<CollectPoint<T>>>);
+
+    impl<T> Clone for Rccp<T> {
+        fn clone(&self) -> Self {
+            Rccp(self.0.clone())
+        }
+    }
+
+    impl<T> Rccp<T> {
+        pub fn new(total: usize) -> Self {
+            Rccp(Rc::new(RefCell::new(CollectPoint::new(total))))
+        }
+        pub fn reset(&self, total: usize) {
+            self.0.borrow_mut().reset(total)
+        }
+        pub fn set(&self, ind: usize, t: T) -> bool {
+            self.0.borrow_mut().set(ind, t)
+        }
+        pub fn get(&self) -> Vec<T> {
+            self.0.borrow_mut().get()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Rc::into_raw(self.0.clone()))
+        }
     }
 
     /// Struct owning and sharing a BigJoinPoint
@@ -370,6 +737,9 @@ mod content2 {
         pub fn incr(&self) -> Option<usize> {
             self.0.incr()
         }
+        pub fn reset(&self) {
+            self.0.reset()
+        }
         pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
             cfgd.get_ind(Rc::into_raw(self.0.clone()))
         }
@@ -379,6 +749,7 @@ mod content2 {
 
 #[cfg(all(feature = "par"))]
 mod content2 {
+    use std::sync::Mutex;
     use std::sync::atomic::AtomicUsize;
     use std::sync::atomic::Ordering::*;
     use super::*;
@@ -399,6 +770,36 @@ mod content2 {
             }
             return None;
         }
+        /// Starts a new join, forgetting the processes of a killed join.
+        pub fn reset(&self) {
+            self.nb.store(0, SeqCst);
+        }
+    }
+
+    pub struct Rccp<T>(Arc<Mutex<CollectPoint<T>>>);
+
+    impl<T: Send> Clone for Rccp<T> {
+        fn clone(&self) -> Self {
+            Rccp(self.0.clone())
+        }
+    }
+
+    impl<T: Send> Rccp<T> {
+        pub fn new(total: usize) -> Self {
+            Rccp(Arc::new(Mutex::new(CollectPoint::new(total))))
+        }
+        pub fn reset(&self, total: usize) {
+            self.0.lock().unwrap().reset(total)
+        }
+        pub fn set(&self, ind: usize, t: T) -> bool {
+            self.0.lock().unwrap().set(ind, t)
+        }
+        pub fn get(&self) -> Vec<T> {
+            self.0.lock().unwrap().get()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Arc::into_raw(self.0.clone()))
+        }
     }
 
     #[derive(Clone)]
@@ -411,6 +812,9 @@ mod content2 {
         pub fn incr(&self) -> Option<usize> {
             self.0.incr()
         }
+        pub fn reset(&self) {
+            self.0.reset()
+        }
         pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
             cfgd.get_ind(Arc::into_raw(self.0.clone()))
         }
@@ -437,3 +841,87 @@ impl<'a> Node<'a, ()> for NBigMerge {
         }
     }
 }
+
+
+/// Node that gives each value of its input to a process of a `process::BigParCollect`,
+/// resets the collect point, and dumps the ids of these processes in the scheduler for
+/// current instant. It panics if there is not exactly one value per process.
+///
+/// Signature : `Vec<In> -> ()`
+pub struct NBigParCollect<In, Out> {
+    pub(crate) rc: Rccp<Out>,
+    pub(crate) cells: Vec<RCell<In>>,
+    pub(crate) dests: Vec<usize>,
+    /// The node to jump to when there is no process
+    pub(crate) end: usize,
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> Node<'a, Vec<In>> for NBigParCollect<In, Out> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, vals: Vec<In>) -> Self::Out {
+        assert_eq!(vals.len(), self.dests.len(), "big_join_collect: one input per process is needed");
+        self.rc.reset(self.dests.len());
+        if self.dests.is_empty() {
+            sub_runtime.add_current(self.end);
+        }
+        for ((cell, d), val) in self.cells.iter().zip(&self.dests).zip(vals) {
+            cell.set(val);
+            sub_runtime.add_current(*d);
+        }
+    }
+}
+
+/// Node that sets the value of the process `ind` in an Rccp, and jumps to dest if it is the
+/// last one to get to the collect point.
+///
+/// Signature : `T -> ()`
+pub struct NCollectSet<T> {
+    rc: Rccp<T>,
+    ind: usize,
+    dest: usize,
+}
+
+pub fn collect<T>(rc: Rccp<T>, ind: usize, dest: usize) -> NCollectSet<T> {
+    NCollectSet { rc, ind, dest }
+}
+
+impl<'a, T: Val<'a>> Node<'a, T> for NCollectSet<T> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: T) -> Self::Out {
+        if self.rc.set(self.ind, val) {
+            sub_runtime.add_current(self.dest);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!(
+            "<f{}> Collect {}: {} in {}",
+            ind,
+            self.ind,
+            tname::<T>(),
+            self.rc.get_ind(cfgd)
+        );
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+/// Node that, when called, extracts the values collected in an Rccp.
+///
+/// Signature : `() -> Vec<T>`
+pub struct NCollectGet<T> {
+    rc: Rccp<T>,
+}
+
+pub fn collect_get<T>(rc: Rccp<T>) -> NCollectGet<T> {
+    NCollectGet { rc }
+}
+
+impl<'a, T: Val<'a>> Node<'a, ()> for NCollectGet<T> {
+    type Out = Vec<T>;
+    fn call(&mut self, _: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.get()
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("CollectGet: {} in {}", tname::<Vec<T>>(), self.rc.get_ind(cfgd))
+    }
+}
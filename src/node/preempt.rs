@@ -0,0 +1,587 @@
+use engine::*;
+use graph::{NodeWrapper, KillHook, Scope};
+use signal::*;
+use super::*;
+
+//   ____                     _
+//  / ___|_   _  __ _ _ __ __| |
+// | |  _| | | |/ _` | '__/ _` |
+// | |_| | |_| | (_| | | | (_| |
+//  \____|\__,_|\__,_|_|  \__,_|
+
+/// Trait for the controllers deciding whether a wrapped main node may run. See `NGuard`.
+///
+/// Any `Guard` can be pushed as a wrapper on the `Graph` while compiling a sub-process.
+pub trait Guard<'a>: Val<'a> + Clone {
+    /// Returns whether the main node of id `pos` may run now.
+    ///
+    /// If it returns `false`, the node call is dropped: the guard is responsible for
+    /// scheduling `pos` again if it should run later.
+    fn check(&self, sub_runtime: &mut SubRuntime<'a>, pos: usize) -> bool;
+
+    /// Returns whether a task of tag `tag` (see `Task`) still belongs to a live execution
+    /// of the guarded sub-graph, it is used to know if a registration it made is still useful.
+    fn alive(&self, _tag: usize) -> bool {
+        true
+    }
+
+    /// Registers `hook` to be run when the guarded sub-graph is killed.
+    fn on_kill(&self, _hook: Box<KillHook>) {}
+}
+
+/// Main node that encapsulates another main node and only runs it when its guard allows it.
+///
+/// Signature : `() -> ()`
+pub struct NGuard<'a, G> {
+    guard: G,
+    pos: usize,
+    node: Box<Node<'a, (), Out = ()>>,
+}
+
+impl<'a, G> Node<'a, ()> for NGuard<'a, G>
+where
+    G: Guard<'a>,
+{
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        if self.guard.check(sub_runtime, self.pos) {
+            self.node.call(sub_runtime, ());
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("Guard|");
+        self.node.printDot(cfgd);
+    }
+}
+
+impl<'a, G> NodeWrapper<'a> for G
+where
+    G: Guard<'a>,
+{
+    fn wrap(&self, pos: usize, node: Box<Node<'a, (), Out = ()>>) -> Box<Node<'a, (), Out = ()>> {
+        box NGuard {
+            guard: self.clone(),
+            pos,
+            node,
+        }
+    }
+    fn clone_wrapper(&self) -> Box<NodeWrapper<'a> + 'a> {
+        box self.clone()
+    }
+    fn alive(&self, tag: usize) -> bool {
+        Guard::alive(self, tag)
+    }
+    fn on_kill(&self, hook: Box<KillHook>) {
+        Guard::on_kill(self, hook)
+    }
+}
+
+
+//  ____                                 _
+// |  _ \ _ __ ___  ___ _ __ ___  _ __ | |_
+// | |_) | '__/ _ \/ _ \ '_ ` _ \| '_ \| __|
+// |  __/| | |  __/  __/ | | | | | |_) | |_
+// |_|   |_|  \___|\___|_| |_| |_| .__/ \__|
+//                               |_|
+
+/// State shared by all the nodes of a preemptible sub-graph (see `process::PAbort`).
+///
+/// Each execution of the body takes a new tag when it is entered (see `Task`). The nodes of
+/// the body are wrapped in `NGuard`s that drop the tasks of an execution that was killed,
+/// including the ones waiting on a signal or on a join point when it was killed, even if the
+/// body has been entered again since.
+pub struct Preempt {
+    /// Whether the body is currently executing.
+    alive: bool,
+    /// The tag of the current execution, the tasks of the body have a greater or equal tag.
+    start: usize,
+    /// The instant in which a preemption was requested for the current execution.
+    kill: Option<usize>,
+    /// The tag of the task that registered the watching node on the signal, if it is registered.
+    registered: Option<usize>,
+    /// The main node to run at the instant following the preemption.
+    handler: Option<usize>,
+    /// The cleanups to run when an execution of the body is killed.
+    hooks: Vec<Box<KillHook>>,
+}
+
+impl Preempt {
+    pub fn new() -> Self {
+        Preempt {
+            alive: false,
+            start: 0,
+            kill: None,
+            registered: None,
+            handler: None,
+            hooks: vec![],
+        }
+    }
+
+    /// Returns whether a task of tag `tag` belongs to the current execution of the body.
+    pub fn running(&self, tag: usize) -> bool {
+        self.alive && tag >= self.start
+    }
+
+    /// Starts a new execution of the body, with the tag `tag`.
+    pub fn enter(&mut self, tag: usize) {
+        self.start = tag;
+        self.alive = true;
+        self.kill = None;
+    }
+
+    /// Returns true if the watching node must be registered on the signal by the task of
+    /// tag `tag`, i.e. if it is not registered or if `valid` says that the task which
+    /// registered it was dropped since.
+    pub fn register<F: Fn(usize) -> bool>(&mut self, tag: usize, valid: F) -> bool {
+        let res = self.registered.map_or(true, |reg| !valid(reg));
+        if res {
+            self.registered = Some(tag);
+        }
+        res
+    }
+
+    /// Marks the body as terminated normally.
+    pub fn leave(&mut self) {
+        self.alive = false;
+    }
+
+    /// Marks the watching node as not registered anymore.
+    pub fn unregister(&mut self) {
+        self.registered = None;
+    }
+
+    /// Requests the preemption of the running body.
+    ///
+    /// Returns the tag of the execution to give to `kill` at the end of the instant,
+    /// or `None` if there is nothing to preempt.
+    pub fn request(&mut self, current_instant: usize) -> Option<usize> {
+        if self.alive && self.kill.is_none() {
+            self.kill = Some(current_instant);
+            Some(self.start)
+        } else {
+            None
+        }
+    }
+
+    /// Kills the execution of tag `start` of the body if it is still running.
+    ///
+    /// Returns the task running the handler at the next instant.
+    pub fn kill(&mut self, start: usize) -> Option<Task> {
+        if self.alive && self.start == start {
+            self.alive = false;
+            self.kill = None;
+            for hook in self.hooks.iter() {
+                hook.killed();
+            }
+            self.handler.map(|node| Task { node, tag: start })
+        } else {
+            None
+        }
+    }
+
+    pub fn set_handler(&mut self, handler: usize) {
+        self.handler = Some(handler);
+    }
+
+    /// Adds a cleanup to run when the body is killed, the useless ones are dropped.
+    pub(crate) fn on_kill(&mut self, hook: Box<KillHook>) {
+        self.hooks.retain(|h| !h.dropped());
+        self.hooks.push(hook);
+    }
+
+    /// Marks the body as terminated normally, unless a preemption was requested for the
+    /// current execution. Returns whether the body left.
+    pub fn leave_unless_requested(&mut self) -> bool {
+        if self.kill.is_none() {
+            self.alive = false;
+        }
+        self.kill.is_none()
+    }
+}
+
+#[cfg(not(feature = "par"))]
+mod content {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use super::*;
+
+    /// Struct sharing and pointing to a `Preempt`
+    pub struct RcPreempt(Rc<RefCell<Preempt>>);
+
+    impl Clone for RcPreempt {
+        fn clone(&self) -> Self {
+            RcPreempt(self.0.clone())
+        }
+    }
+
+    impl RcPreempt {
+        pub fn new() -> Self {
+            RcPreempt(Rc::new(RefCell::new(Preempt::new())))
+        }
+        pub fn running(&self, tag: usize) -> bool {
+            self.0.borrow().running(tag)
+        }
+        pub fn enter(&self, tag: usize) {
+            self.0.borrow_mut().enter(tag)
+        }
+        pub fn register<F: Fn(usize) -> bool>(&self, tag: usize, valid: F) -> bool {
+            self.0.borrow_mut().register(tag, valid)
+        }
+        pub fn leave(&self) {
+            self.0.borrow_mut().leave()
+        }
+        pub fn unregister(&self) {
+            self.0.borrow_mut().unregister()
+        }
+        pub fn request(&self, current_instant: usize) -> Option<usize> {
+            self.0.borrow_mut().request(current_instant)
+        }
+        pub fn kill(&self, start: usize) -> Option<Task> {
+            self.0.borrow_mut().kill(start)
+        }
+        pub fn set_handler(&self, handler: usize) {
+            self.0.borrow_mut().set_handler(handler)
+        }
+        pub(crate) fn on_kill(&self, hook: Box<KillHook>) {
+            self.0.borrow_mut().on_kill(hook)
+        }
+        pub fn leave_unless_requested(&self) -> bool {
+            self.0.borrow_mut().leave_unless_requested()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Rc::into_raw(self.0.clone()))
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod content {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use super::*;
+
+    /// Struct sharing and pointing to a `Preempt`
+    pub struct RcPreempt(Arc<Mutex<Preempt>>);
+
+    impl Clone for RcPreempt {
+        fn clone(&self) -> Self {
+            RcPreempt(self.0.clone())
+        }
+    }
+
+    impl RcPreempt {
+        pub fn new() -> Self {
+            RcPreempt(Arc::new(Mutex::new(Preempt::new())))
+        }
+        pub fn running(&self, tag: usize) -> bool {
+            self.0.lock().unwrap().running(tag)
+        }
+        pub fn enter(&self, tag: usize) {
+            self.0.lock().unwrap().enter(tag)
+        }
+        pub fn register<F: Fn(usize) -> bool>(&self, tag: usize, valid: F) -> bool {
+            self.0.lock().unwrap().register(tag, valid)
+        }
+        pub fn leave(&self) {
+            self.0.lock().unwrap().leave()
+        }
+        pub fn unregister(&self) {
+            self.0.lock().unwrap().unregister()
+        }
+        pub fn request(&self, current_instant: usize) -> Option<usize> {
+            self.0.lock().unwrap().request(current_instant)
+        }
+        pub fn kill(&self, start: usize) -> Option<Task> {
+            self.0.lock().unwrap().kill(start)
+        }
+        pub fn set_handler(&self, handler: usize) {
+            self.0.lock().unwrap().set_handler(handler)
+        }
+        pub(crate) fn on_kill(&self, hook: Box<KillHook>) {
+            self.0.lock().unwrap().on_kill(hook)
+        }
+        pub fn leave_unless_requested(&self) -> bool {
+            self.0.lock().unwrap().leave_unless_requested()
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Arc::into_raw(self.0.clone()))
+        }
+    }
+}
+
+pub use self::content::*;
+
+impl<'a> Guard<'a> for RcPreempt {
+    fn check(&self, sub_runtime: &mut SubRuntime<'a>, _: usize) -> bool {
+        self.running(sub_runtime.get_current_tag())
+    }
+    fn alive(&self, tag: usize) -> bool {
+        self.running(tag)
+    }
+    fn on_kill(&self, hook: Box<KillHook>) {
+        RcPreempt::on_kill(self, hook)
+    }
+}
+
+/// End of instant continuation that kills a preempted body and starts its handler.
+pub struct PreemptEoi {
+    rc: RcPreempt,
+    start: usize,
+}
+
+impl<'a> EndOfInstantCallback<'a> for PreemptEoi {
+    fn on_end_of_instant(&self, sub_runtime: &mut SubRuntime<'a>) {
+        if let Some(handler) = self.rc.kill(self.start) {
+            sub_runtime.add_current_task(handler);
+        }
+    }
+}
+
+/// Requests the preemption of the body controlled by `rc`.
+///
+/// The body finishes its instant and is killed at the end of the current instant.
+pub fn request_preemption<'a>(rc: &RcPreempt, sub_runtime: &mut SubRuntime<'a>) -> bool {
+    if let Some(start) = rc.request(sub_runtime.get_current_instant()) {
+        sub_runtime.add_eoi(box PreemptEoi { rc: rc.clone(), start });
+        true
+    } else {
+        false
+    }
+}
+
+
+//  _____       _
+// | ____|_ __ | |_ ___ _ __
+// |  _| | '_ \| __/ _ \ '__|
+// | |___| | | | ||  __/ |
+// |_____|_| |_|\__\___|_|
+
+/// Node starting the execution of a preemptible body with a new tag, and registering the
+/// watching node `watch` on the signal if needed.
+///
+/// `scope` is the scope of the preemption construct, where the watching node runs.
+///
+/// Signature : `T -> T`
+pub struct NPreemptEnter<'a, S> {
+    pub(crate) rc: RcPreempt,
+    pub(crate) signal: S,
+    pub(crate) watch: usize,
+    pub(crate) scope: Scope<'a>,
+}
+
+impl<'a, In: Val<'a>, S> Node<'a, In> for NPreemptEnter<'a, S>
+where
+    S: Signal<'a>,
+{
+    type Out = In;
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: In) -> In {
+        let tag = sub_runtime.new_tag();
+        self.rc.enter(tag);
+        let scope = &self.scope;
+        if self.rc.register(tag, |reg| scope.alive(reg)) {
+            self.signal.await_immediate(sub_runtime, self.watch);
+        }
+        val
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> Enter {}", ind, self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.watch));
+    }
+}
+
+
+// __        __    _       _
+// \ \      / /_ _| |_ ___| |__
+//  \ \ /\ / / _` | __/ __| '_ \
+//   \ V  V / (_| | || (__| | | |
+//    \_/\_/ \__,_|\__\___|_| |_|
+
+/// Main node called when the signal controlling a preemptible body is emitted.
+///
+/// Signature : `() -> ()`
+pub struct NPreemptWatch {
+    pub rc: RcPreempt,
+}
+
+impl<'a> Node<'a, ()> for NPreemptWatch {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.unregister();
+        request_preemption(&self.rc, sub_runtime);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("Watch {}", self.rc.get_ind(cfgd));
+    }
+}
+
+
+//  _
+// | |    ___  __ ___   _____
+// | |   / _ \/ _` \ \ / / _ \
+// | |__|  __/ (_| |\ V /  __/
+// |_____\___|\__,_| \_/ \___|
+
+/// Node marking a preemptible body as terminated normally.
+///
+/// Signature : `() -> ()`
+pub struct NLeave {
+    rc: RcPreempt,
+}
+
+/// Builds a `NLeave`
+pub fn leave(rc: RcPreempt) -> NLeave {
+    NLeave { rc }
+}
+
+impl<'a> Node<'a, ()> for NLeave {
+    type Out = ();
+    fn call(&mut self, _: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.leave();
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("Leave {}", self.rc.get_ind(cfgd));
+    }
+}
+
+
+//  ____                                 _
+// / ___| _   _ ___ _ __   ___ _ __   __| |
+// \___ \| | | / __| '_ \ / _ \ '_ \ / _` |
+//  ___) | |_| \__ \ |_) |  __/ | | | (_| |
+// |____/ \__,_|___/ .__/ \___|_| |_|\__,_|
+//                 |_|
+
+/// Guard letting a node run only in instants where the signal is present
+/// (see `process::PWhen`).
+///
+/// When the signal is absent, the node waits for its next emission.
+#[derive(Clone)]
+pub struct WhenGuard<S>(pub S);
+
+impl<'a, S> Guard<'a> for WhenGuard<S>
+where
+    S: Signal<'a> + Clone,
+{
+    fn check(&self, sub_runtime: &mut SubRuntime<'a>, pos: usize) -> bool {
+        if self.0.is_set(sub_runtime.get_current_instant()) {
+            true
+        } else {
+            self.0.await_immediate(sub_runtime, pos);
+            false
+        }
+    }
+}
+
+
+//  _____
+// |_   _| __ __ _ _ __
+//   | || '__/ _` | '_ \
+//   | || | | (_| | |_) |
+//   |_||_|  \__,_| .__/
+//                |_|
+
+/// A trap that can be exited with a value of type `V` (see `process::PTrap`).
+///
+/// It is shared between the `PTrap` construct and all the `exit` processes of its body.
+pub struct Trap<V> {
+    pub(crate) rc: RcPreempt,
+    pub(crate) val: RCell<V>,
+}
+
+impl<V> Clone for Trap<V> {
+    fn clone(&self) -> Self {
+        Trap {
+            rc: self.rc.clone(),
+            val: self.val.clone(),
+        }
+    }
+}
+
+impl<V> Trap<V> {
+    pub fn new() -> Self {
+        Trap {
+            rc: RcPreempt::new(),
+            val: RCell::new(),
+        }
+    }
+}
+
+/// Node starting the execution of the body of a trap with a new tag.
+///
+/// Signature : `T -> T`
+pub struct NTrapEnter {
+    pub rc: RcPreempt,
+}
+
+impl<'a, In: Val<'a>> Node<'a, In> for NTrapEnter {
+    type Out = In;
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: In) -> In {
+        let tag = sub_runtime.new_tag();
+        self.rc.enter(tag);
+        val
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("Trap {}", self.rc.get_ind(cfgd));
+    }
+}
+
+/// Node called when the body of a trap terminates normally: it stores the value and
+/// jumps to `dest`, unless the trap was exited in this instant.
+///
+/// Signature : `V -> ()`
+pub struct NTrapLeave<V> {
+    pub trap: Trap<V>,
+    pub dest: usize,
+}
+
+impl<'a, V: Val<'a>> Node<'a, V> for NTrapLeave<V> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: V) -> Self::Out {
+        if self.trap.rc.leave_unless_requested() {
+            self.trap.val.set(val);
+            sub_runtime.add_current(self.dest);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> Leave {}", ind, self.trap.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+/// Node exiting a trap with its input value. Only the first exit of an instant is kept.
+///
+/// Signature : `V -> ()`
+pub struct NExit<V> {
+    pub trap: Trap<V>,
+}
+
+impl<'a, V: Val<'a>> Node<'a, V> for NExit<V> {
+    type Out = ();
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, val: V) -> Self::Out {
+        if request_preemption(&self.trap.rc, sub_runtime) {
+            self.trap.val.set(val);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        print!("Exit {}", self.trap.rc.get_ind(cfgd));
+    }
+}
+
+/// Node ending a process that never terminates, it must never be called.
+///
+/// Signature : `() -> O`
+pub struct NNever<Out>(PhantomData<Out>);
+
+pub fn nnever<Out>() -> NNever<Out> {
+    NNever(PhantomData)
+}
+
+impl<'a, Out: Val<'a>> Node<'a, ()> for NNever<Out> {
+    type Out = Out;
+    fn call(&mut self, _: &mut SubRuntime<'a>, _: ()) -> Out {
+        panic!("the end of a process that never terminates was scheduled, \
+                its reserved id must never be jumped to")
+    }
+}
@@ -1,4 +1,5 @@
 use engine::*;
+use graph::Scope;
 use signal::*;
 use super::*;
 
@@ -341,6 +342,43 @@ where
     }
 }
 
+/// Main node checking with `pred` the value of the signal at the last instant.
+/// If the predicate holds, the value is stored in `rc` and `dest` is called in the current instant,
+/// otherwise the node awaits the next emission of the signal. `id` must be the id of this node.
+/// The value is read even if it is rejected, see `process::AwaitSInWhen`.
+///
+/// Signature : `() -> ()`
+pub struct NAwaitSWhen<S, F, V> {
+    pub signal: S,
+    pub pred: F,
+    pub rc: RCell<V>,
+    pub id: usize,
+    pub dest: usize,
+}
+
+impl<'a, S, F: Val<'a>, V: Val<'a>> Node<'a, ()> for NAwaitSWhen<S, F, V>
+where
+    S: Signal<'a, V = V>,
+    F: FnMut(&V) -> bool,
+{
+    type Out = ();
+
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        let v = self.signal.get_pre_value(sub_runtime.get_current_instant());
+        if (self.pred)(&v) {
+            self.rc.set(v);
+            sub_runtime.add_current(self.dest);
+        } else {
+            self.signal.await(sub_runtime, self.id);
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> AwaitWhen : {} in {}", ind, tname::<V>(), self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
 
 //     _                _ _   ___                              _ _       _       ____
 //    / \__      ____ _(_) |_|_ _|_ __ ___  _ __ ___   ___  __| (_) __ _| |_ ___|  _ \
@@ -447,3 +485,421 @@ where
         );
     }
 }
+
+
+//  _____                __        __    _ _
+// | ____|_  ___ __  _ _\ \      / /_ _(_) |_
+// |  _| \ \/ / '_ \| '__\ \ /\ / / _` | | __|
+// | |___ >  <| |_) | |   \ V  V / (_| | | |_
+// |_____/_/\_\ .__/|_|    \_/\_/ \__,_|_|\__|
+//            |_|
+
+/// State shared by the nodes testing or awaiting a `SignalExpr`.
+///
+/// A single watching node is registered on the signals of the expression,
+/// and `registered` keeps the instant and the tag of its last registration on each of them.
+/// A registration made by a task that was dropped since by a guard of `scope` is made again.
+pub struct ExprWait<'a> {
+    expr: SignalExpr<'a>,
+    registered: Vec<Option<(usize, usize)>>,
+    /// The scope of the watching node
+    scope: Scope<'a>,
+    /// Whether a `present` or an `await` is waiting for the expression
+    active: bool,
+    /// The instant when the current wait started
+    start: usize,
+    /// The tag of the task that started the current wait, given to the nodes it jumps to
+    tag: usize,
+    /// The last instant when an `await` checked the expression
+    checked: Option<usize>,
+}
+
+impl<'a> ExprWait<'a> {
+    pub(crate) fn new(expr: SignalExpr<'a>, scope: Scope<'a>) -> Self {
+        let registered = vec![None; expr.leaves().len()];
+        ExprWait {
+            expr,
+            registered,
+            scope,
+            active: false,
+            start: 0,
+            tag: 0,
+            checked: None,
+        }
+    }
+
+    /// Forgets the registrations consumed by an emission.
+    ///
+    /// A signal emitted at instant `emitted` consumed the registrations made until `emitted`.
+    fn consume<F: Fn(&PureSignal<'a>) -> Option<usize>>(&mut self, emitted: F) {
+        for (reg, s) in self.registered.iter_mut().zip(self.expr.leaves()) {
+            if let (Some((r, _)), Some(e)) = (*reg, emitted(s)) {
+                if r <= e {
+                    *reg = None;
+                }
+            }
+        }
+    }
+
+    /// Registers `watch` on the signals of the expression where it is not registered yet.
+    ///
+    /// If `immediate` is true, signals already emitted in the current instant are skipped.
+    fn register(&mut self, sub_runtime: &mut SubRuntime<'a>, watch: usize, immediate: bool) {
+        let instant = sub_runtime.get_current_instant();
+        let tag = sub_runtime.get_current_tag();
+        let scope = &self.scope;
+        for (reg, s) in self.registered.iter_mut().zip(self.expr.leaves()) {
+            if reg.map_or(true, |(_, t)| !scope.alive(t)) {
+                if immediate {
+                    if !s.is_set(instant) {
+                        s.await_immediate(sub_runtime, watch);
+                        *reg = Some((instant, tag));
+                    }
+                } else {
+                    s.await(sub_runtime, watch);
+                    *reg = Some((instant, tag));
+                }
+            }
+        }
+    }
+
+    /// Jumps to the branch given by `value`, if it is known.
+    fn decide(&mut self, sub_runtime: &mut SubRuntime<'a>, value: Option<bool>, node_true: usize, node_false: usize) -> bool {
+        match value {
+            Some(true) => sub_runtime.add_current_task(Task { node: node_true, tag: self.tag }),
+            Some(false) => sub_runtime.add_next_task(Task { node: node_false, tag: self.tag }),
+            None => return false,
+        }
+        self.active = false;
+        true
+    }
+
+    /// Starts a `present` on the expression.
+    ///
+    /// Returns true if the expression cannot be decided yet,
+    /// then `present_eoi` must be called at the end of the instant.
+    pub fn present_start(&mut self, sub_runtime: &mut SubRuntime<'a>, watch: usize, node_true: usize, node_false: usize) -> bool {
+        let instant = sub_runtime.get_current_instant();
+        self.active = true;
+        self.start = instant;
+        self.tag = sub_runtime.get_current_tag();
+        let value = self.expr.eval(&|s| if s.is_set(instant) { Some(true) } else { None });
+        if self.decide(sub_runtime, value, node_true, node_false) {
+            return false;
+        }
+        self.register(sub_runtime, watch, true);
+        true
+    }
+
+    /// Called when a signal of the expression is emitted during a `present`.
+    pub fn present_watch(&mut self, sub_runtime: &mut SubRuntime<'a>, node_true: usize, node_false: usize) {
+        let instant = sub_runtime.get_current_instant();
+        self.consume(|s| if s.is_set(instant) { Some(instant) } else { None });
+        if self.active && self.start == instant {
+            let value = self.expr.eval(&|s| if s.is_set(instant) { Some(true) } else { None });
+            self.decide(sub_runtime, value, node_true, node_false);
+        }
+    }
+
+    /// Called at the end of the instant `instant`, where the signals not emitted are absent.
+    ///
+    /// Both branches then start at the next instant.
+    pub fn present_eoi(&mut self, sub_runtime: &mut SubRuntime<'a>, instant: usize, node_true: usize, node_false: usize) {
+        if self.active && self.start == instant {
+            self.active = false;
+            let node = if self.expr.eval(&|s| Some(s.is_set(instant))) == Some(true) {
+                node_true
+            } else {
+                node_false
+            };
+            sub_runtime.add_current_task(Task { node, tag: self.tag });
+        }
+    }
+
+    /// Registers the watching node of an `await`.
+    ///
+    /// If the expression holds when no signal is emitted, the watching node checks it at
+    /// every instant instead.
+    fn arm(&mut self, sub_runtime: &mut SubRuntime<'a>, watch: usize) {
+        if self.expr.eval(&|_| Some(false)) == Some(true) {
+            sub_runtime.add_next_task(Task { node: watch, tag: self.tag });
+            return;
+        }
+        self.register(sub_runtime, watch, false);
+    }
+
+    /// Starts an `await` on the expression.
+    pub fn await_start(&mut self, sub_runtime: &mut SubRuntime<'a>, watch: usize) {
+        self.active = true;
+        self.start = sub_runtime.get_current_instant();
+        self.tag = sub_runtime.get_current_tag();
+        self.arm(sub_runtime, watch);
+    }
+
+    /// Called at the instant following the emission of a signal of the expression
+    /// during an `await`.
+    pub fn await_watch(&mut self, sub_runtime: &mut SubRuntime<'a>, watch: usize, dest: usize) {
+        let instant = sub_runtime.get_current_instant();
+        if self.checked == Some(instant) {
+            return;
+        }
+        self.checked = Some(instant);
+        self.consume(|s| if s.pre_set(instant) { Some(instant - 1) } else { None });
+        if !self.active {
+            return;
+        }
+        if self.start < instant && self.expr.eval(&|s| Some(s.pre_set(instant))) == Some(true) {
+            self.active = false;
+            sub_runtime.add_current_task(Task { node: dest, tag: self.tag });
+        } else {
+            self.arm(sub_runtime, watch);
+        }
+    }
+}
+
+#[cfg(not(feature = "par"))]
+mod content {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use super::*;
+
+    /// Struct sharing and pointing to an `ExprWait`
+    pub struct RcExprWait<'a>(Rc<RefCell<ExprWait<'a>>>);
+
+    impl<'a> Clone for RcExprWait<'a> {
+        fn clone(&self) -> Self {
+            RcExprWait(self.0.clone())
+        }
+    }
+
+    impl<'a> RcExprWait<'a> {
+        pub(crate) fn new(expr: SignalExpr<'a>, scope: Scope<'a>) -> Self {
+            RcExprWait(Rc::new(RefCell::new(ExprWait::new(expr, scope))))
+        }
+        pub fn present_start(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize, node_true: usize, node_false: usize) -> bool {
+            self.0.borrow_mut().present_start(sub_runtime, watch, node_true, node_false)
+        }
+        pub fn present_watch(&self, sub_runtime: &mut SubRuntime<'a>, node_true: usize, node_false: usize) {
+            self.0.borrow_mut().present_watch(sub_runtime, node_true, node_false)
+        }
+        pub fn present_eoi(&self, sub_runtime: &mut SubRuntime<'a>, instant: usize, node_true: usize, node_false: usize) {
+            self.0.borrow_mut().present_eoi(sub_runtime, instant, node_true, node_false)
+        }
+        pub fn await_start(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize) {
+            self.0.borrow_mut().await_start(sub_runtime, watch)
+        }
+        pub fn await_watch(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize, dest: usize) {
+            self.0.borrow_mut().await_watch(sub_runtime, watch, dest)
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Rc::into_raw(self.0.clone()))
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod content {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use super::*;
+
+    /// Struct sharing and pointing to an `ExprWait`
+    pub struct RcExprWait<'a>(Arc<Mutex<ExprWait<'a>>>);
+
+    impl<'a> Clone for RcExprWait<'a> {
+        fn clone(&self) -> Self {
+            RcExprWait(self.0.clone())
+        }
+    }
+
+    impl<'a> RcExprWait<'a> {
+        pub(crate) fn new(expr: SignalExpr<'a>, scope: Scope<'a>) -> Self {
+            RcExprWait(Arc::new(Mutex::new(ExprWait::new(expr, scope))))
+        }
+        pub fn present_start(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize, node_true: usize, node_false: usize) -> bool {
+            self.0.lock().unwrap().present_start(sub_runtime, watch, node_true, node_false)
+        }
+        pub fn present_watch(&self, sub_runtime: &mut SubRuntime<'a>, node_true: usize, node_false: usize) {
+            self.0.lock().unwrap().present_watch(sub_runtime, node_true, node_false)
+        }
+        pub fn present_eoi(&self, sub_runtime: &mut SubRuntime<'a>, instant: usize, node_true: usize, node_false: usize) {
+            self.0.lock().unwrap().present_eoi(sub_runtime, instant, node_true, node_false)
+        }
+        pub fn await_start(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize) {
+            self.0.lock().unwrap().await_start(sub_runtime, watch)
+        }
+        pub fn await_watch(&self, sub_runtime: &mut SubRuntime<'a>, watch: usize, dest: usize) {
+            self.0.lock().unwrap().await_watch(sub_runtime, watch, dest)
+        }
+        pub fn get_ind(&self, cfgd: &mut CFGDrawer) -> usize {
+            cfgd.get_ind(Arc::into_raw(self.0.clone()))
+        }
+    }
+}
+
+pub use self::content::*;
+
+/// End of instant continuation deciding a `present` that is still unknown.
+pub struct PresentExprEoi<'a> {
+    rc: RcExprWait<'a>,
+    instant: usize,
+    node_true: usize,
+    node_false: usize,
+}
+
+impl<'a> EndOfInstantCallback<'a> for PresentExprEoi<'a> {
+    fn on_end_of_instant(&self, sub_runtime: &mut SubRuntime<'a>) {
+        self.rc.present_eoi(sub_runtime, self.instant, self.node_true, self.node_false);
+    }
+}
+
+
+//  ____                           _   _____
+// |  _ \ _ __ ___  ___  ___ _ __ | |_| ____|_  ___ __  _ __
+// | |_) | '__/ _ \/ __|/ _ \ '_ \| __|  _| \ \/ / '_ \| '__|
+// |  __/| | |  __/\__ \  __/ | | | |_| |___ >  <| |_) | |
+// |_|   |_|  \___||___/\___|_| |_|\__|_____/_/\_\ .__/|_|
+//                                               |_|
+
+/// Node jumping to node_true if the expression holds in the current instant,
+/// and jumping to node_false at the next instant otherwise.
+/// When the expression is only decided at the end of the instant,
+/// both branches start at the next instant.
+#[derive(Clone)]
+pub struct NPresentExpr<'a> {
+    pub rc: RcExprWait<'a>,
+    pub watch: usize,
+    pub node_true: usize,
+    pub node_false: usize,
+}
+
+impl<'a> Node<'a, ()> for NPresentExpr<'a> {
+    type Out = ();
+
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        if self.rc.present_start(sub_runtime, self.watch, self.node_true, self.node_false) {
+            sub_runtime.add_eoi(box PresentExprEoi {
+                rc: self.rc.clone(),
+                instant: sub_runtime.get_current_instant(),
+                node_true: self.node_true,
+                node_false: self.node_false,
+            });
+        }
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> PresentExpr {}", ind, self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.node_true));
+        cfgd.add_arrow((ind, self.node_false));
+    }
+}
+
+/// Node registered on the signals of a `present` expression.
+#[derive(Clone)]
+pub struct NPresentExprWatch<'a> {
+    pub rc: RcExprWait<'a>,
+    pub node_true: usize,
+    pub node_false: usize,
+}
+
+impl<'a> Node<'a, ()> for NPresentExprWatch<'a> {
+    type Out = ();
+
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.present_watch(sub_runtime, self.node_true, self.node_false);
+    }
+}
+
+
+//     _                _ _   _____
+//    / \__      ____ _(_) |_| ____|_  ___ __  _ __
+//   / _ \ \ /\ / / _` | | __|  _| \ \/ / '_ \| '__|
+//  / ___ \ V  V / (_| | | |_| |___ >  <| |_) | |
+// /_/   \_\_/\_/ \__,_|_|\__|_____/_/\_\ .__/|_|
+//                                      |_|
+
+/// Node starting to await an expression.
+/// The watching node jumps to the next node at the instant following the one where
+/// the expression holds.
+#[derive(Clone)]
+pub struct NAwaitExpr<'a> {
+    pub rc: RcExprWait<'a>,
+    pub watch: usize,
+}
+
+impl<'a> Node<'a, ()> for NAwaitExpr<'a> {
+    type Out = ();
+
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.await_start(sub_runtime, self.watch);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> AwaitExpr {}", ind, self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.watch));
+    }
+}
+
+/// Node registered on the signals of an awaited expression.
+#[derive(Clone)]
+pub struct NAwaitExprWatch<'a> {
+    pub rc: RcExprWait<'a>,
+    pub id: usize,
+    pub dest: usize,
+}
+
+impl<'a> Node<'a, ()> for NAwaitExprWatch<'a> {
+    type Out = ();
+
+    fn call(&mut self, sub_runtime: &mut SubRuntime<'a>, _: ()) -> Self::Out {
+        self.rc.await_watch(sub_runtime, self.id, self.dest);
+    }
+    fn printDot(&mut self, cfgd: &mut CFGDrawer) {
+        let ind = cfgd.get_node_ind();
+        print!("<f{}> AwaitExprWatch {}", ind, self.rc.get_ind(cfgd));
+        cfgd.add_arrow((ind, self.dest));
+    }
+}
+
+
+//  _                    _ ____  _                   _
+// | |    ___   ___ __ _| / ___|(_) __ _ _ __   __ _| |
+// | |   / _ \ / __/ _` | \___ \| |/ _` | '_ \ / _` | |
+// | |__| (_) | (_| (_| | |___) | | (_| | | | | (_| | |
+// |_____\___/ \___\__,_|_|____/|_|\__, |_| |_|\__,_|_|
+//                                 |___/
+
+/// Node entering the scope of a local signal: it puts a fresh signal built by `factory`
+/// in the handle, and gives the handle with the input value.
+///
+/// Signature : `In -> (LocalSignal<S>,In)`
+pub struct NLocalSignalEnter<F, S> {
+    pub factory: F,
+    pub signal: LocalSignal<S>,
+}
+
+impl<'a, F: Val<'a>, S: Val<'a> + Clone, In: Val<'a>> Node<'a, In> for NLocalSignalEnter<F, S>
+where
+    F: FnMut() -> S,
+{
+    type Out = (LocalSignal<S>, In);
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> Self::Out {
+        self.signal.set(Some((self.factory)()));
+        (self.signal.clone(), val)
+    }
+}
+
+/// Node leaving the scope of a local signal, the signal is dropped from the handle.
+///
+/// Signature : `T -> T`
+pub struct NLocalSignalLeave<S> {
+    pub signal: LocalSignal<S>,
+}
+
+impl<'a, S: Val<'a> + Clone, In: Val<'a>> Node<'a, In> for NLocalSignalLeave<S> {
+    type Out = In;
+    fn call(&mut self, _: &mut SubRuntime<'a>, val: In) -> In {
+        self.signal.set(None);
+        val
+    }
+}
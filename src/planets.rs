@@ -35,7 +35,7 @@ impl Planet {
 const GRAVITATION_CONST: f32 = 6.67;
 const DT: f32 = 0.1;
 
-type EnvSignal = SignalRuntimeRef<MCSignalValue<Planet, Vec<Planet>>>;
+type EnvSignal = SignalRuntimeRef<SharedSignalValue<Planet, Vec<Planet>>>;
 
 
 fn random_speed<R: Rng>(rng: &mut R) -> [f32; 3] {
@@ -94,7 +94,7 @@ fn next_pos(planet: &mut Planet, planets: &Vec<Planet>) {
 
 
 fn main() {
-    let env = EnvSignal::new_mc(vec![], box |emit_value: Planet, current_value: &mut Vec<Planet>| {
+    let env = EnvSignal::new_shared(vec![], box |emit_value: Planet, current_value: &mut Vec<Planet>| {
         current_value.push(emit_value);
     });
     let mut rng = rand::thread_rng();
@@ -125,7 +125,7 @@ fn main() {
             };
             emit_s_in(env.clone());
             await_s_in(env.clone());
-            |(planets, mut current_planet): (Vec<Planet>, Planet)| -> ChoiceData<Planet,()> {
+            |(planets, mut current_planet): (Snapshot<Vec<Planet>>, Planet)| -> ChoiceData<Planet,()> {
                 next_pos(&mut current_planet, &planets);
                 True(current_planet)
             }
@@ -1,6 +1,8 @@
 use node::*;
 use super::*;
 
+use std::marker::PhantomData;
+
 //  _   _       _   _     _
 // | \ | | ___ | |_| |__ (_)_ __   __ _
 // |  \| |/ _ \| __| '_ \| | '_ \ / _` |
@@ -265,3 +267,43 @@ impl<'a, In: Val<'a>> IntProcessNotIm<'a, In> for PPause {
     }
 }
 
+
+//  _   _
+// | \ | | _____   _____ _ __
+// |  \| |/ _ \ \ / / _ \ '__|
+// | |\  |  __/\ V /  __/ |
+// |_| \_|\___| \_/ \___|_|
+
+/// A process that never terminates, it is only useful in a preempted body.
+/// It ignores its input and can have any output type.
+#[derive(Copy, Clone)]
+pub(crate) struct PNever<Out>(PhantomData<Out>);
+
+/// Builds a boxed `PNever`, this is `halt` in ReactiveML.
+pub fn never<'a, In: Val<'a>, Out: Val<'a>>()
+                                            -> ProcessNotIm<'a, In, Out, NotOnce, Ignore, NNever<Out>>
+{
+    ProcessNotIm(box PNever(PhantomData))
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PNever<Out> {
+    type Out = Out;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"Never\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> IntProcessNotIm<'a, In> for PNever<Out> {
+    type NI = Ignore;
+    type NO = NNever<Out>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        // the end of the process is never scheduled.
+        let out = g.reserve();
+        (Ignore {}, out, nnever())
+    }
+}
@@ -282,3 +282,317 @@ implIm!{
 
     }
 }
+
+
+//  _____
+// |  ___|__  _ __
+// | |_ / _ \| '__|
+// |  _| (_) | |
+// |_|  \___/|_|
+
+/// A process implementation that runs `body` once for each item of `iter`.
+///
+/// The loop carries a value: the body takes a pair (item, value) and returns the value for the
+/// next iteration. `iter` is cloned each time the loop starts.
+///
+/// Signature is In -> In when body : (I::Item, In) -> In.
+pub struct PFor<I, P> {
+    pub(crate) iter: I,
+    pub(crate) body: P,
+}
+
+impl<'a, I: Val<'a>, P, In: Val<'a>> IntProcess<'a, In> for PFor<I, P>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Val<'a>,
+    I::Item: Val<'a>,
+    P: Process<'a, (I::Item, In), Out = In>,
+{
+    type Out = In;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (beg, end) = self.body.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [label = \"for\"]", numbeg);
+        println!("{}:s -> {} [label = \"{}\"]", numbeg, beg, tname::<(I::Item, In)>());
+        println!("{} [shape=diamond]", numend);
+        println!("{} -> {}:n [label = \"{}\"]", end, numend, tname::<In>());
+        println!("{}:w -> {}:w [label = \"Next\"];", numend, numbeg);
+        (numbeg, numend)
+    }
+}
+
+implNI!{
+    In,
+    impl<'a, I: Val<'a>, In: Val<'a>, MarkOnce, PNI, PNO>
+        for PFor<I, ProcessNotIm<'a, (I::Item, In), In, MarkOnce, PNI, PNO>>
+        where
+        I: IntoIterator + Clone,
+        I::IntoIter: Val<'a>,
+        I::Item: Val<'a>,
+        MarkOnce: Once,
+        PNI: Node<'a, (I::Item, In), Out = ()>,
+        PNO: Node<'a, (), Out = In>,
+    trait IntProcessNotIm<'a, In> {
+        type NI = NSeq<NSeq<NForInit<I, I::IntoIter>, NStore<In>>, NJump>;
+        type NO = NLoad<In>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO){
+            let s = *self;
+            let (pni, pind, pno) = s.body.compile(g);
+            let rcit = RCell::new();
+            let rcextin = RCell::new();
+            let rcbegin = rcextin.clone();
+            let rcendin = rcextin.clone();
+            let rcendout = RCell::new();
+            let rcextout = rcendout.clone();
+            let out_id = g.reserve();
+            let in_id = g.add(box node!(
+                load(rcbegin) >> NForStep { it: rcit.clone() } >> choice {
+                    pni
+                }{
+                    store(rcendout) >> njump(out_id)
+                }));
+            g.set(pind, box node!(pno >> store(rcendin) >> njump(in_id)));
+            let init = NForInit { iter: s.iter, it: rcit };
+            (
+                node!(init >> store(rcextin) >> njump(in_id)),
+                out_id,
+                load(rcextout)
+            )
+        }
+    }
+}
+
+implIm!{
+    In,
+    impl<'a, I: Val<'a>, In: Val<'a>, MarkOnce, PNIO>
+        for PFor<I, ProcessIm<'a, (I::Item, In), In, MarkOnce, PNIO>>
+        where
+        I: IntoIterator + Clone,
+        I::IntoIter: Val<'a>,
+        I::Item: Val<'a>,
+        MarkOnce: Once,
+        PNIO: Node<'a, (I::Item, In), Out = In>,
+    trait IntProcessIm<'a, In> {
+        type NIO = NSeq<NForInit<I, I::IntoIter>,
+                        LoopIm<NSeq<NForStep<I::IntoIter>,
+                                    NChoice<NSeq<PNIO, NContinue<In>>, NBreak<In>>>>>;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO{
+            let s = *self;
+            let pnio = s.body.compileIm(g);
+            let rcit = RCell::new();
+            let step = node!(NForStep { it: rcit.clone() } >> choice {
+                pnio >> ncontinue()
+            }{
+                nbreak()
+            });
+            node!(NForInit { iter: s.iter, it: rcit } >> LoopIm(step))
+        }
+    }
+}
+
+/// Builds a `PFor` running `body` for each item of `iter`.
+/// this is equivalent to pro!{for (iter) {body}}
+pub fn pfor<'a, I: Val<'a>, P, In: Val<'a>>(iter: I, body: P) -> <PFor<I, P> as ToBoxedProcess<'a, In>>::Boxed
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Val<'a>,
+    I::Item: Val<'a>,
+    P: Process<'a, (I::Item, In), Out = In>,
+    PFor<I, P>: ToBoxedProcess<'a, In>,
+{
+    PFor { iter, body }.tobox()
+}
+
+
+// __        ___     _ _
+// \ \      / / |__ (_) | ___
+//  \ \ /\ / /| '_ \| | |/ _ \
+//   \ V  V / | | | | | |  __/
+//    \_/\_/  |_| |_|_|_|\___|
+
+/// A process implementation that runs `body` while `cond` holds on the loop value.
+///
+/// The condition is checked before each iteration, the body returns the value for the next one.
+///
+/// Signature is In -> In when body : In -> In.
+pub struct PWhile<F, P> {
+    pub(crate) cond: F,
+    pub(crate) body: P,
+}
+
+impl<'a, F: Val<'a>, P, In: Val<'a>> IntProcess<'a, In> for PWhile<F, P>
+where
+    F: FnMut(&In) -> bool,
+    P: Process<'a, In, Out = In>,
+{
+    type Out = In;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (beg, end) = self.body.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [label = \"while\", shape=diamond]", numbeg);
+        println!("{}:s -> {} [label = \"{}\"]", numbeg, beg, tname::<In>());
+        println!("{} [size = 0.1]", numend);
+        println!("{}:w -> {}:w [label = \"{}\"];", end, numbeg, tname::<In>());
+        println!("{}:e -> {} [label = \"Exit\"];", numbeg, numend);
+        (numbeg, numend)
+    }
+}
+
+implNI!{
+    In,
+    impl<'a, F: Val<'a>, In: Val<'a>, MarkOnce, PNI, PNO>
+        for PWhile<F, ProcessNotIm<'a, In, In, MarkOnce, PNI, PNO>>
+        where
+        F: FnMut(&In) -> bool,
+        MarkOnce: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = In>,
+    trait IntProcessNotIm<'a, In> {
+        type NI = NSeq<NStore<In>, NJump>;
+        type NO = NLoad<In>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO){
+            let s = *self;
+            let (pni, pind, pno) = s.body.compile(g);
+            let rcextin = RCell::new();
+            let rcbegin = rcextin.clone();
+            let rcendin = rcextin.clone();
+            let rcendout = RCell::new();
+            let rcextout = rcendout.clone();
+            let out_id = g.reserve();
+            let in_id = g.add(box node!(
+                load(rcbegin) >> NWhileStep(s.cond) >> choice {
+                    pni
+                }{
+                    store(rcendout) >> njump(out_id)
+                }));
+            g.set(pind, box node!(pno >> store(rcendin) >> njump(in_id)));
+            (
+                node!(store(rcextin) >> njump(in_id)),
+                out_id,
+                load(rcextout)
+            )
+        }
+    }
+}
+
+implIm!{
+    In,
+    impl<'a, F: Val<'a>, In: Val<'a>, MarkOnce, PNIO>
+        for PWhile<F, ProcessIm<'a, In, In, MarkOnce, PNIO>>
+        where
+        F: FnMut(&In) -> bool,
+        MarkOnce: Once,
+        PNIO: Node<'a, In, Out = In>,
+    trait IntProcessIm<'a, In> {
+        type NIO = LoopIm<NSeq<NWhileStep<F>, NChoice<NSeq<PNIO, NContinue<In>>, NBreak<In>>>>;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO{
+            let s = *self;
+            let pnio = s.body.compileIm(g);
+            LoopIm(node!(NWhileStep(s.cond) >> choice {
+                pnio >> ncontinue()
+            }{
+                nbreak()
+            }))
+        }
+    }
+}
+
+/// Builds a `PWhile` running `body` while `cond` holds.
+/// this is equivalent to pro!{while (cond) {body}}
+pub fn pwhile<'a, F: Val<'a>, P, In: Val<'a>>(cond: F, body: P) -> <PWhile<F, P> as ToBoxedProcess<'a, In>>::Boxed
+where
+    F: FnMut(&In) -> bool,
+    P: Process<'a, In, Out = In>,
+    PWhile<F, P>: ToBoxedProcess<'a, In>,
+{
+    PWhile { cond, body }.tobox()
+}
+
+
+//  ____          _ _       _
+// / ___|_      _(_) |_ ___| |__
+// \___ \ \ /\ / / | __/ __| '_ \
+//  ___) \ V  V /| | || (__| | | |
+// |____/ \_/\_/ |_|\__\___|_| |_|
+
+/// A multi-way branching struct: `selector` maps the input to a branch index and a payload,
+/// and the branch of this index is run on the payload.
+///
+/// All the branches are compiled in the graph and the dispatch is done by a single
+/// node, whatever the number of branches. The selector must return a valid index, see `pswitch`.
+///
+/// Signature is In -> Out when selector : In -> (usize, T) and the branches : T -> Out.
+pub struct PSwitch<'a, F, T, Out> {
+    pub(crate) selector: F,
+    pub(crate) branches: Vec<BoxedBranch<'a, T, Out>>,
+}
+
+impl<'a, F: Val<'a>, T: Val<'a>, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PSwitch<'a, F, T, Out>
+where
+    F: FnMut(In) -> (usize, T),
+{
+    type Out = Out;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = diamond, label= \"Switch({})\"];", num, self.branches.len());
+        (num, num)
+    }
+}
+
+impl<'a, F: Val<'a>, T: Val<'a>, In: Val<'a>, Out: Val<'a>> IntProcessNotIm<'a, In> for PSwitch<'a, F, T, Out>
+where
+    F: FnMut(In) -> (usize, T),
+{
+    type NI = NSwitch<F, T>;
+    type NO = NLoad<Out>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let s = *self;
+        let rcin = RCell::new();
+        let rcout = RCell::new();
+        let out_id = g.reserve();
+        let mut dests = vec![];
+        for p in s.branches {
+            let pni = p.compile_branch(g, box node!(store(rcout.clone()) >> njump(out_id)));
+            dests.push(g.add(box node!(load(rcin.clone()) >> pni)));
+        }
+        let ni = NSwitch {
+            selector: s.selector,
+            rc: rcin,
+            dests,
+        };
+        (ni, out_id, load(rcout))
+    }
+}
+
+/// Builds a `PSwitch` dispatching its input between `branches` with `selector`.
+/// this is equivalent to pro!{match (selector) {P0} {P1} ...}, where each branch is boxed
+/// with `boxed_branch`.
+///
+/// `selector` must return an index lower than `branches.len()`: add a last branch as the
+/// default case if some inputs have no branch of their own.
+///
+/// # Panics
+///
+/// The process panics if `selector` returns an index with no branch.
+pub fn pswitch<'a, F: Val<'a>, T: Val<'a>, In: Val<'a>, Out: Val<'a>, B>(
+    selector: F,
+    branches: Vec<B>,
+) -> ProcessNotIm<'a, In, Out, NotOnce, NSwitch<F, T>, NLoad<Out>>
+where
+    F: FnMut(In) -> (usize, T),
+    B: Branch<'a, T, Out>,
+{
+    let branches = branches.into_iter().map(boxed_branch).collect();
+    ProcessNotIm(box PSwitch { selector, branches })
+}
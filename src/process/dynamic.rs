@@ -0,0 +1,202 @@
+use node::*;
+use signal::*;
+use super::*;
+
+use std::marker::PhantomData;
+
+//  _____ _       _   _
+// |  ___| | __ _| |_| |_ ___ _ __
+// | |_  | |/ _` | __| __/ _ \ '_ \
+// |  _| | | (_| | |_| ||  __/ | | |
+// |_|   |_|\__,_|\__|\__\___|_| |_|
+
+/// Process running the process it receives as input value, and returning its output.
+///
+/// The input process is compiled at runtime, when the value is received, and starts
+/// in the same instant. Each new input process reuses the nodes of the previous one, so
+/// the runtime only grows when an input process is bigger than all the previous ones.
+///
+/// Signature is P -> P::Out where P is a `Spawnable` process.
+#[derive(Clone, Copy)]
+pub struct PFlatten {}
+
+impl<'a, P> IntProcess<'a, P> for PFlatten
+where
+    P: Spawnable<'a>,
+{
+    type Out = P::Out;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"Flatten\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, P> IntProcessNotIm<'a, P> for PFlatten
+where
+    P: Spawnable<'a>,
+{
+    type NI = NFlatten<'a, P::Out>;
+    type NO = NLoad<P::Out>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let rc = RCell::new();
+        let out_id = g.reserve();
+        let ni = NFlatten {
+            rc: rc.clone(),
+            dest: out_id,
+            guard: RcPreempt::new(),
+            ids: vec![],
+            wrappers: g.clone_wrappers(),
+        };
+        (ni, out_id, load(rc))
+    }
+}
+
+/// Builds boxed `PFlatten`
+pub fn flatten<'a, P>() -> ProcessNotIm<'a, P, P::Out, NotOnce, NFlatten<'a, P::Out>, NLoad<P::Out>>
+where
+    P: Spawnable<'a>,
+{
+    ProcessNotIm(box PFlatten {})
+}
+
+
+//  ____
+// / ___| _ __   __ ___      ___ __   ___ _ __
+// \___ \| '_ \ / _` \ \ /\ / / '_ \ / _ \ '__|
+//  ___) | |_) | (_| |\ V  V /| | | |  __/ |
+// |____/| .__/ \__,_| \_/\_/ |_| |_|\___|_|
+//       |_|
+
+/// A boxed process that can be compiled at runtime, as emitted on a spawn signal
+/// (see `SignalRuntimeRef::new_spawn`).
+pub type BoxedSpawn<'a> = Box<Spawnable<'a, Out = ()> + 'a>;
+
+/// Process starting, at each instant, all the processes emitted on `signal` during
+/// the previous instant. They run in parallel with the spawner and are never joined.
+/// The nodes of a process that terminated, or was killed, are reused by the next ones.
+///
+/// This is `loop { await add(p) in run p }` in ReactiveML. The spawner never terminates.
+#[derive(Clone, Copy)]
+pub struct PSpawner<S>(pub S);
+
+impl<'a, S: Val<'a>> IntProcess<'a, ()> for PSpawner<S>
+where
+    S: Signal<'a, V = Vec<BoxedSpawn<'a>>>,
+{
+    type Out = ();
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"Spawner\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, S: Val<'a>> IntProcessNotIm<'a, ()> for PSpawner<S>
+where
+    S: Signal<'a, V = Vec<BoxedSpawn<'a>>> + Clone,
+{
+    type NI = NAwaitS<S>;
+    type NO = Nothing;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let signal = self.0;
+        let spawn_id = g.reserve();
+        let wrappers = g.clone_wrappers();
+        g.set(
+            spawn_id,
+            box NSpawn {
+                signal: signal.clone(),
+                id: spawn_id,
+                slots: vec![],
+                wrappers,
+            },
+        );
+        // the output node is never called, the spawner never terminates
+        let out_id = g.reserve();
+        (NAwaitS(signal, spawn_id), out_id, Nothing {})
+    }
+}
+
+/// Builds boxed `PSpawner`
+pub fn spawner<'a, S: Val<'a>>(signal: S) -> ProcessNotIm<'a, (), (), NotOnce, NAwaitS<S>, Nothing>
+where
+    S: Signal<'a, V = Vec<BoxedSpawn<'a>>> + Clone,
+{
+    ProcessNotIm(box PSpawner(signal))
+}
+
+
+//  ____            __  __
+// |  _ \ __ _ _ __|  \/  | __ _ _ __
+// | |_) / _` | '__| |\/| |/ _` | '_ \
+// |  __/ (_| | |  | |  | | (_| | |_) |
+// |_|   \__,_|_|  |_|  |_|\__,_| .__/
+//                              |_|
+
+/// Process mapping a vector with one process per value, all running in parallel.
+///
+/// The processes are built by the factory `F`, and take their value as input.
+/// It waits that all processes have finished before returning the vector of their results,
+/// in the same order. In the parallel runtime, the processes are started on all the threads
+/// in turn (see `SubRuntime::add_current_spread`).
+///
+/// The processes are compiled at runtime, when a vector longer than all the previous ones is
+/// received, and are reused afterwards: the factory must build `NotOnce` processes
+/// (see `Branch`). The vector may have any length.
+///
+/// Signature is Vec<T> -> Vec<U>.
+pub struct PParMap<F, U>(pub(crate) F, pub(crate) PhantomData<U>);
+
+impl<'a, T: Val<'a>, U: Val<'a>, F: Val<'a>, P> IntProcess<'a, Vec<T>> for PParMap<F, U>
+where
+    F: FnMut() -> P,
+    P: Branch<'a, T, U>,
+{
+    type Out = Vec<U>;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"ParMap\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, T: Val<'a>, U: Val<'a>, F: Val<'a>, P> IntProcessNotIm<'a, Vec<T>> for PParMap<F, U>
+where
+    F: FnMut() -> P,
+    P: Branch<'a, T, U>,
+{
+    type NI = NParMap<'a, T, U, F>;
+    type NO = NCollectGet<U>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let rc = Rccp::new(0);
+        let out_id = g.reserve();
+        let ni = NParMap {
+            factory: self.0,
+            cells: vec![],
+            starts: vec![],
+            rc: rc.clone(),
+            dest: out_id,
+            wrappers: g.clone_wrappers(),
+        };
+        (ni, out_id, collect_get(rc))
+    }
+}
+
+/// Builds boxed `PParMap`
+pub fn par_map<'a, T: Val<'a>, U: Val<'a>, F: Val<'a>, P>(factory: F)
+    -> ProcessNotIm<'a, Vec<T>, Vec<U>, NotOnce, NParMap<'a, T, U, F>, NCollectGet<U>>
+where
+    F: FnMut() -> P,
+    P: Branch<'a, T, U>,
+{
+    ProcessNotIm(box PParMap(factory, PhantomData))
+}
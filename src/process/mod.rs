@@ -50,7 +50,7 @@ mod seq;
 use self::seq::*;
 
 
-/// Contains parallel structures i.e `||` and `BigPar`
+/// Contains parallel structures i.e `||`, `race`, `BigPar` and `BigParCollect`
 mod par;
 #[doc(hidden)]
 pub use self::par::*;
@@ -60,6 +60,16 @@ mod signal;
 #[doc(hidden)]
 pub use self::signal::*;
 
+/// Contains preemption structures: `abort`, `when`, `until`, `every`, `trap`, ...
+mod preempt;
+#[doc(hidden)]
+pub use self::preempt::*;
+
+/// Contains structures running processes compiled at runtime: `flatten`, `spawner`, ...
+mod dynamic;
+#[doc(hidden)]
+pub use self::dynamic::*;
+
 //   ___
 //  / _ \ _ __   ___ ___
 // | | | | '_ \ / __/ _ \
@@ -124,7 +134,7 @@ impl GiveOnce for And<NotOnce, NotOnce> {
 
 
 /// Common interface for processes and process implementations.
-pub trait IntProcess<'a, In: Val<'a>>: 'a {
+pub trait IntProcess<'a, In: Val<'a>>: Val<'a> {
     /// The type outputted by the process when In is given.
     type Out: Val<'a>;
 
@@ -246,6 +256,105 @@ pub trait ToBoxedProcess<'a, In: Val<'a>>: IntProcess<'a, In> + Sized {
 // |_|   |_|  \___/ \___\___||___/___/   |_||_|  \__,_|_|\__|
 
 
+/// `p; never()`, the body of `Process::loop_each` before it is aborted.
+type LoopEachSeq<'a, P> = Seq<
+    P,
+    ProcessNotIm<
+        'a,
+        <P as IntProcess<'a, ()>>::Out,
+        ChoiceData<(), ()>,
+        NotOnce,
+        Ignore,
+        NNever<ChoiceData<(), ()>>,
+    >,
+>;
+
+/// The body of the loop built by `Process::loop_each`: `p; never()` aborted by `s`, with a
+/// handler restarting the loop.
+type LoopEachBody<'a, P, S> = <PAbort<
+    <LoopEachSeq<'a, P> as ToBoxedProcess<'a, ()>>::Boxed,
+    ProcessIm<'a, (), ChoiceData<(), ()>, NotOnce, NValue<ChoiceData<(), ()>>>,
+    S,
+> as ToBoxedProcess<'a, ()>>::Boxed;
+
+/// The process built by `Process::loop_each`.
+type LoopEach<'a, P, S> = <PLoop<LoopEachBody<'a, P, S>> as ToBoxedProcess<'a, ()>>::Boxed;
+
+/// `await_s(s)`, the initial wait of `Process::every`.
+type EveryAwait<'a, S> = ProcessNotIm<
+    'a,
+    (),
+    <S as Signal<'a>>::V,
+    NotOnce,
+    NAwaitS<S>,
+    NGetS<S>,
+>;
+
+/// The initial wait of `Process::every`: `await_s(s)`, whose value is dropped.
+type EveryWait<'a, S> = <Seq<
+    EveryAwait<'a, S>,
+    ProcessIm<'a, <S as Signal<'a>>::V, (), NotOnce, NFnMut<fn(<S as Signal<'a>>::V)>>,
+> as ToBoxedProcess<'a, ()>>::Boxed;
+
+/// The process built by `Process::every`.
+type Every<'a, P, S> = <Seq<EveryWait<'a, S>, LoopEach<'a, P, S>> as ToBoxedProcess<'a, ()>>::Boxed;
+
+/// Drops the value of the signal awaited by `Process::every`.
+fn drop_value<V>(_: V) {}
+
+/// Trait of the processes that can be restarted on the emissions of a signal of type `S`,
+/// see `Process::loop_each` and `Process::every`.
+///
+/// It is implemented by all the processes taking `()` as input, it only gathers the bounds
+/// needed to build the composed processes.
+pub trait Restart<'a, S>: Process<'a, ()> {
+    /// The process built by `loop_each`.
+    type LoopEach: Process<'a, (), Out = ()>;
+    /// The process built by `every`.
+    type Every: Process<'a, (), Out = ()>;
+
+    /// Builds the process `loop_each (s) {self}`.
+    fn restart_loop_each(self, signal: S) -> Self::LoopEach;
+
+    /// Builds the process `every (s) {self}`.
+    fn restart_every(self, signal: S) -> Self::Every;
+}
+
+impl<'a, P, S> Restart<'a, S> for P
+where
+    P: Process<'a, ()>,
+    S: Signal<'a> + Clone,
+    LoopEachSeq<'a, P>: ToBoxedProcess<'a, ()>,
+    PAbort<
+        <LoopEachSeq<'a, P> as ToBoxedProcess<'a, ()>>::Boxed,
+        ProcessIm<'a, (), ChoiceData<(), ()>, NotOnce, NValue<ChoiceData<(), ()>>>,
+        S,
+    >: ToBoxedProcess<'a, ()>,
+    PLoop<LoopEachBody<'a, P, S>>: ToBoxedProcess<'a, ()>,
+    LoopEach<'a, P, S>: Process<'a, (), Out = ()>,
+    Seq<
+        EveryAwait<'a, S>,
+        ProcessIm<'a, S::V, (), NotOnce, NFnMut<fn(S::V)>>,
+    >: ToBoxedProcess<'a, ()>,
+    Seq<EveryWait<'a, S>, LoopEach<'a, P, S>>: ToBoxedProcess<'a, ()>,
+    Every<'a, P, S>: Process<'a, (), Out = ()>,
+{
+    type LoopEach = LoopEach<'a, P, S>;
+    type Every = Every<'a, P, S>;
+
+    fn restart_loop_each(self, signal: S) -> Self::LoopEach {
+        let body = <P as Process<'a, ()>>::seq(self, never());
+        body.abort(signal, value(ChoiceData::True(()))).ploop()
+    }
+
+    fn restart_every(self, signal: S) -> Self::Every {
+        let wait: EveryAwait<'a, S> = ProcessNotIm(box AwaitS(signal.clone()));
+        let wait = wait.seq(fnmut2pro(drop_value::<S::V> as fn(S::V)));
+        wait.seq(self.restart_loop_each(signal))
+    }
+}
+
+
 /// General trait for a process.
 ///
 /// An end-user should only care about this trait.
@@ -262,6 +371,19 @@ pub trait Process<'a, In: Val<'a>>: IntProcess<'a, In> + Sized {
         Seq(self, p).tobox()
     }
 
+    /// a.seq_try(b) execute a then b if a returned `Ok`, it corresponds to pro!{a?;b}
+    ///
+    /// The value in the `Ok` of a is given as input to b. If a returns `Err(e)`, b is
+    /// skipped and the construct returns `Err(e)`.
+    fn seq_try<P, T: Val<'a>, U: Val<'a>, E: Val<'a>>(self, p: P) -> <SeqTry<Self, P> as ToBoxedProcess<'a, In>>::Boxed
+    where
+        Self: Process<'a, In, Out = Result<T, E>>,
+        P: Process<'a, T, Out = Result<U, E>>,
+        SeqTry<Self, P>: ToBoxedProcess<'a, In>,
+    {
+        SeqTry(self, p).tobox()
+    }
+
 
     /// a.choice(b) execute a or b depending on the input value.
     /// If we input True(x), a will run with input x.
@@ -302,6 +424,17 @@ pub trait Process<'a, In: Val<'a>>: IntProcess<'a, In> + Sized {
         Par(self, q).tobox()
     }
 
+    /// a.race(b) execute a and b in parallel like `join`, but returns as soon as one of them
+    /// is finished: `True` with the result of a, or `False` with the result of b.
+    /// The other one is cancelled at the end of the instant (see `Race`).
+    fn race<InQ: Val<'a>, Q>(self, q: Q) -> <Race<Self, Q> as ToBoxedProcess<'a, (In, InQ)>>::Boxed
+    where
+        Q: Process<'a, InQ>,
+        Race<Self, Q>: ToBoxedProcess<'a, (In, InQ)>,
+    {
+        Race(self, q).tobox()
+    }
+
     /// a.present(b) build a present (`PresentD`) construct with a and b.
     /// this is equivalent to pro!{present{a}{b}}
     fn present<PF, S: Signal<'a>>(
@@ -318,6 +451,101 @@ pub trait Process<'a, In: Val<'a>>: IntProcess<'a, In> + Sized {
              pf: process_false,
          }).tobox()
     }
+
+    /// a.present_expr(e, b) build a present on a signal expression (`PresentExpr`) with a and b.
+    /// this is equivalent to pro!{present (e) {a}{b}}
+    fn present_expr<PF>(
+        self,
+        expr: SignalExpr<'a>,
+        process_false: PF,
+    ) -> <PresentExpr<'a, Self, PF> as ToBoxedProcess<'a, ()>>::Boxed
+    where
+        PF: Process<'a, ()>,
+        Self: Process<'a, ()>,
+        PresentExpr<'a, Self, PF>: ToBoxedProcess<'a, ()>,
+    {
+        (PresentExpr {
+             pt: self,
+             pf: process_false,
+             expr,
+         }).tobox()
+    }
+
+    /// a.abort(s, h) runs a until s is emitted, a is then killed at the end of the instant (see `PAbort`).
+    /// If a is preempted, h is run at the next instant and gives the output value.
+    /// this is equivalent to pro!{abort (s) {a}{h}}
+    fn abort<H, S>(self, signal: S, handler: H) -> <PAbort<Self, H, S> as ToBoxedProcess<'a, In>>::Boxed
+    where
+        H: Process<'a, (), Out = Self::Out>,
+        S: Signal<'a>,
+        PAbort<Self, H, S>: ToBoxedProcess<'a, In>,
+    {
+        (PAbort {
+             body: self,
+             handler,
+             signal,
+         }).tobox()
+    }
+
+    /// a.when(s) runs a only in the instants where s is present (see `PWhen`).
+    /// this is equivalent to pro!{when (s) {a}}
+    fn when<S>(self, signal: S) -> <PWhen<Self, S> as ToBoxedProcess<'a, In>>::Boxed
+    where
+        S: Signal<'a>,
+        PWhen<Self, S>: ToBoxedProcess<'a, In>,
+    {
+        (PWhen { body: self, signal }).tobox()
+    }
+
+    /// a.until(s, h) runs a until s is emitted (weak preemption, see `PUntil`).
+    /// If a is preempted, it finishes its instant and h is run at the next instant,
+    /// with the value of s and s itself.
+    /// this is equivalent to pro!{until (s) {a}{h}}
+    fn until<H, S>(self, signal: S, handler: H) -> <PUntil<Self, H, S> as ToBoxedProcess<'a, In>>::Boxed
+    where
+        S: Signal<'a>,
+        H: Process<'a, (S::V, S), Out = Self::Out>,
+        PUntil<Self, H, S>: ToBoxedProcess<'a, In>,
+    {
+        (PUntil {
+             body: self,
+             handler,
+             signal,
+         }).tobox()
+    }
+
+    /// a.trap(t) runs a until it terminates or exits t (see `PTrap`).
+    /// If t is exited with v, a is killed at the end of the instant and v is
+    /// returned at the next instant.
+    /// this is equivalent to pro!{trap (t) {a}}
+    fn trap(self, trap: Trap<Self::Out>) -> <PTrap<Self, Self::Out> as ToBoxedProcess<'a, In>>::Boxed
+    where
+        PTrap<Self, Self::Out>: ToBoxedProcess<'a, In>,
+    {
+        (PTrap { body: self, trap }).tobox()
+    }
+
+    /// a.every(s) waits for s, then runs a and restarts it at the instant following each
+    /// new emission of s. The running a is killed at the end of the instant. It never terminates.
+    /// this is equivalent to pro!{every (s) {a}},
+    /// and to pro!{await_s(s); |_| (); loop_each (s) {a}}
+    fn every<S>(self, signal: S) -> <Self as Restart<'a, S>>::Every
+    where
+        Self: Restart<'a, S>,
+    {
+        self.restart_every(signal)
+    }
+
+    /// a.loop_each(s) runs a and restarts it at the instant following each emission of s.
+    /// The running a is killed at the end of the instant. It never terminates.
+    /// this is equivalent to pro!{loop_each (s) {a}},
+    /// and to pro!{loop { abort (s) {a; never()} {value(True(()))} }}
+    fn loop_each<S>(self, signal: S) -> <Self as Restart<'a, S>>::LoopEach
+    where
+        Self: Restart<'a, S>,
+    {
+        self.restart_loop_each(signal)
+    }
 }
 
 /// Puts a lot of processes in parallel, they can take a copy `In` value and must return ().
@@ -337,24 +565,107 @@ where
     ProcessNotIm(box BigPar(res))
 }
 
+/// Puts a lot of processes in parallel, each one taking its own value of the input vector,
+/// and returns the vector of their results, in the same order.
+///
+/// The processes can be of different types by boxing them as `BoxedBranch`, they must be
+/// `NotOnce` (see `Branch`).
+///
+/// # Panics
+///
+/// The process panics if its input vector does not have exactly one value per process.
+pub fn big_join_collect<'a, In: Val<'a>, Out: Val<'a>, B>(
+    vp: Vec<B>,
+) -> ProcessNotIm<'a, Vec<In>, Vec<Out>, NotOnce, NBigParCollect<In, Out>, NCollectGet<Out>>
+where
+    B: Branch<'a, In, Out>,
+{
+    ProcessNotIm(box BigParCollect(vp.into_iter().map(boxed_branch).collect()))
+}
+
 
 /// this trait is implemented by something that be compiled into a full
 /// Control Flow Graph.
 pub trait GraphFiller<'a> : 'a {
+    /// The type outputted by the whole process.
+    type Out: Val<'a>;
+
     /// Compile Self to a `Graph` and return the index of starting `Node`
-    fn compile_to_graph(self) -> (Graph<'a>, usize);
+    ///
+    /// The value outputted by the process is stored in `result` when it ends.
+    fn compile_to_graph(self, result: RCell<Self::Out>) -> (Graph<'a>, usize);
 }
 
-default impl<'a, T> GraphFiller<'a> for T
+default impl<'a, T, Out: Val<'a>> GraphFiller<'a> for T
 where
-    T: Process<'a, (), Out = ()>,
+    T: Process<'a, (), Out = Out>,
 {
-    fn compile_to_graph(self) -> (Graph<'a>, usize){
+    type Out = Out;
+    fn compile_to_graph(self, _result: RCell<Out>) -> (Graph<'a>, usize){
         // only ProcessIm and ProcessNotIm implement Process
         unreachable!()
     }
 }
 
+/// Trait for processes that can be compiled while the runtime is running (see `flatten`).
+///
+/// It is implemented by the processes of input type `()`.
+pub trait Spawnable<'a>: Val<'a> {
+    /// The type outputted by the process.
+    type Out: Val<'a>;
+
+    /// Compiles the process in `g`, calling `end` on its output value when it terminates.
+    ///
+    /// Returns the id of the main node starting the process.
+    fn compile_spawn(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Self::Out, Out = ()>>) -> usize;
+}
+
+impl<'a, S: ?Sized> Spawnable<'a> for Box<S>
+where
+    S: Spawnable<'a>,
+{
+    type Out = S::Out;
+    fn compile_spawn(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Self::Out, Out = ()>>) -> usize {
+        S::compile_spawn(*self, g, end)
+    }
+}
+
+/// A process that can be put in a `big_join_collect` together with processes of other types.
+///
+/// It is implemented by `ProcessIm` and `ProcessNotIm`, thus a `Vec<BoxedBranch<In, Out>>` can
+/// mix immediate and non-immediate processes. Only the `NotOnce` processes are branches: a branch
+/// is compiled once but the construct using it may run it again, in a loop or for several values
+/// of a `par_map`.
+pub trait Branch<'a, In: Val<'a>, Out: Val<'a>>: Val<'a> {
+    /// Compiles the process in `g`, calling `end` on its output value when it terminates.
+    ///
+    /// Returns the node starting the process on its input value.
+    fn compile_branch(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>)
+        -> Box<Node<'a, In, Out = ()>>;
+}
+
+/// A boxed process with erased type, see `Branch`.
+pub type BoxedBranch<'a, In, Out> = Box<Branch<'a, In, Out> + 'a>;
+
+/// Boxes a process as a `BoxedBranch`, to put processes of different types in a `Vec`.
+pub fn boxed_branch<'a, In: Val<'a>, Out: Val<'a>, B>(b: B) -> BoxedBranch<'a, In, Out>
+where
+    B: Branch<'a, In, Out>,
+{
+    box b
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>, B: ?Sized> Branch<'a, In, Out> for Box<B>
+where
+    B: Branch<'a, In, Out>,
+{
+    fn compile_branch(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>)
+        -> Box<Node<'a, In, Out = ()>>
+    {
+        B::compile_branch(*self, g, end)
+    }
+}
+
 //  ___ __  __ ____  _       ____              ___
 // |_ _|  \/  |  _ \| |     |  _ \ _ __ ___   |_ _|_ __ ___
 //  | || |\/| | |_) | |     | |_) | '__/ _ \   | || '_ ` _ \
@@ -387,19 +698,45 @@ where
 {}
 
 #[cfg_attr(rustfmt, rustfmt_skip)]
-impl<'a, MarkOnce, NIO> GraphFiller<'a> for ProcessIm<'a, (), (), MarkOnce, NIO>
+impl<'a, Out: Val<'a>, MarkOnce, NIO> GraphFiller<'a> for ProcessIm<'a, (), Out, MarkOnce, NIO>
     where
     MarkOnce: Once,
-    NIO: Node<'a, (), Out = ()>,
+    NIO: Node<'a, (), Out = Out>,
 {
-    fn compile_to_graph(self) -> (Graph<'a>, usize){
+    type Out = Out;
+    fn compile_to_graph(self, result: RCell<Out>) -> (Graph<'a>, usize){
         let mut g = Graph::new();
         let pnio = self.compileIm(&mut g);
-        let start = g.add(box node!(pnio >> NEnd{}));
+        let start = g.add(box node!(pnio >> nend(result)));
         (g,start)
     }
 }
 
+#[cfg_attr(rustfmt, rustfmt_skip)]
+impl<'a, Out: Val<'a>, MarkOnce, NIO> Spawnable<'a> for ProcessIm<'a, (), Out, MarkOnce, NIO>
+    where
+    MarkOnce: Once,
+    NIO: Node<'a, (), Out = Out>,
+{
+    type Out = Out;
+    fn compile_spawn(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>) -> usize {
+        let pnio = self.compileIm(g);
+        g.add(box node!(pnio >> end))
+    }
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>, NIO> Branch<'a, In, Out> for ProcessIm<'a, In, Out, NotOnce, NIO>
+    where
+    NIO: Node<'a, In, Out = Out>,
+{
+    fn compile_branch(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>)
+        -> Box<Node<'a, In, Out = ()>>
+    {
+        let pnio = self.compileIm(g);
+        box node!(pnio >> end)
+    }
+}
+
 
 //  ___ __  __ ____  _       ____              _   _ ___
 // |_ _|  \/  |  _ \| |     |  _ \ _ __ ___   | \ | |_ _|
@@ -432,21 +769,50 @@ where
 {
 }
 
-impl<'a, MarkOnce, NI, NO> GraphFiller<'a> for ProcessNotIm<'a, (), (), MarkOnce, NI, NO>
+impl<'a, Out: Val<'a>, MarkOnce, NI, NO> GraphFiller<'a> for ProcessNotIm<'a, (), Out, MarkOnce, NI, NO>
 where
     NI: Node<'a, (), Out = ()>,
-    NO: Node<'a, (), Out = ()>,
+    NO: Node<'a, (), Out = Out>,
     MarkOnce: Once,
 {
-    fn compile_to_graph(self) -> (Graph<'a>, usize){
+    type Out = Out;
+    fn compile_to_graph(self, result: RCell<Out>) -> (Graph<'a>, usize){
         let mut g = Graph::new();
         let (pni, pind, pno) = self.compile(&mut g);
-        g.set(pind, box node!(pno >> NEnd{}));
+        g.set(pind, box node!(pno >> nend(result)));
         let start = g.add(box pni);
         (g,start)
     }
 }
 
+impl<'a, Out: Val<'a>, MarkOnce, NI, NO> Spawnable<'a> for ProcessNotIm<'a, (), Out, MarkOnce, NI, NO>
+where
+    NI: Node<'a, (), Out = ()>,
+    NO: Node<'a, (), Out = Out>,
+    MarkOnce: Once,
+{
+    type Out = Out;
+    fn compile_spawn(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>) -> usize {
+        let (pni, pind, pno) = self.compile(g);
+        g.set(pind, box node!(pno >> end));
+        g.add(box pni)
+    }
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>, NI, NO> Branch<'a, In, Out> for ProcessNotIm<'a, In, Out, NotOnce, NI, NO>
+where
+    NI: Node<'a, In, Out = ()>,
+    NO: Node<'a, (), Out = Out>,
+{
+    fn compile_branch(self: Box<Self>, g: &mut Graph<'a>, end: Box<Node<'a, Out, Out = ()>>)
+        -> Box<Node<'a, In, Out = ()>>
+    {
+        let (pni, pind, pno) = self.compile(g);
+        g.set(pind, box node!(pno >> end));
+        box pni
+    }
+}
+
 
 //  ____       _       _    ____                 _
 // |  _ \ _ __(_)_ __ | |_ / ___|_ __ __ _ _ __ | |__
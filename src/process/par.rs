@@ -69,7 +69,7 @@ implNI!{
 
     trait IntProcessNotIm<'a, (InP,InQ)>
     {
-        type NI = NSeq<NPar<PNI, QNI>, Ignore>;
+        type NI = NSeq<NJoinStart<OutP, OutQ>, NSeq<NPar<PNI, QNI>, Ignore>>;
         type NO = NMerge<OutP, OutQ>;
         fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
             let s = *self;
@@ -82,7 +82,8 @@ implNI!{
             let rcout = rc1.clone();
             g.set(pind, box node!(pno >> set1(rc1, out_ind)));
             g.set(qind, box node!(qno >> set2(rc2, out_ind)));
-            (nodei!(pni || qni), out_ind, merge(rcout))
+            let branches = nodei!(pni || qni);
+            (node!(join_start(rcout.clone()) >> branches), out_ind, merge(rcout))
         }
 
     }
@@ -190,6 +191,197 @@ implIm!{
 
 
 
+//  ____
+// |  _ \ __ _  ___ ___
+// | |_) / _` |/ __/ _ \
+// |  _ < (_| | (_|  __/
+// |_| \_\__,_|\___\___|
+
+/// A process implementation that runs two processes in parallel and keeps the first one
+/// to finish.
+///
+/// It takes a pair of value (a,b) and returns `True(P(a))` if P finishes first, `False(Q(b))`
+/// otherwise. If both finish in the same instant, the first one to reach its end wins.
+/// The other process keeps running until the end of the instant, and is then cancelled.
+pub struct Race<P, Q>(pub(crate) P, pub(crate) Q);
+
+impl<'a, P, Q, InP: Val<'a>, InQ: Val<'a>, OutP: Val<'a>, OutQ: Val<'a>> IntProcess<'a, (InP, InQ)>
+    for Race<P, Q>
+where
+    P: Process<'a, InP, Out = OutP>,
+    Q: Process<'a, InQ, Out = OutQ>,
+{
+    type Out = ChoiceData<OutP, OutQ>;
+    type MarkOnce = <And<P::MarkOnce, Q::MarkOnce> as GiveOnce>::Once;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (begp, endp) = self.0.printDot(curNum);
+        let (begq, endq) = self.1.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = triangle, label = \"race\"]", numbeg);
+        println!("{}:sw -> {}:n [label = \"{}\"]", numbeg, begp, tname::<InP>());
+        println!("{}:se -> {}:n [label = \"{}\"]", numbeg, begq, tname::<InQ>());
+        println!("{} [shape= invtriangle, label = \"first\"]", numend);
+        println!("{}:s -> {}:nw [label = \"{}\"]", endp, numend, tname::<OutP>());
+        println!("{}:s -> {}:ne [label = \"{}\"]", endq, numend, tname::<OutQ>());
+        (numbeg, numend)
+    }
+}
+
+// NI - NI
+implNI!{
+    (InP,InQ),
+    impl<'a, InP: Val<'a>, InQ: Val<'a>, OutP: Val<'a>, OutQ: Val<'a>,
+         MarkOnceP, MarkOnceQ, PNI, PNO, QNI, QNO>
+        for Race<ProcessNotIm<'a, InP, OutP, MarkOnceP, PNI, PNO>,
+                 ProcessNotIm<'a, InQ, OutQ, MarkOnceQ, QNI, QNO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNI: Node<'a, InP, Out = ()>,
+        PNO: Node<'a, (), Out = OutP>,
+        QNI: Node<'a, InQ, Out = ()>,
+        QNO: Node<'a, (), Out = OutQ>,
+
+    trait IntProcessNotIm<'a, (InP,InQ)>
+    {
+        type NI = NSeq<NRaceStart<OutP, OutQ>, NSeq<NPar<PNI, QNI>, Ignore>>;
+        type NO = NRaceGet<OutP, OutQ>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let Race(p, q) = *self;
+            let out_ind = g.reserve();
+            let rc = Rcrp::new();
+            g.push_wrapper(box rc.clone());
+            let (pni, pind, pno) = p.compile(g);
+            let (qni, qind, qno) = q.compile(g);
+            g.set(pind, box node!(pno >> race1(rc.clone(), out_ind)));
+            g.set(qind, box node!(qno >> race2(rc.clone(), out_ind)));
+            g.pop_wrapper();
+            let branches = nodei!(pni || qni);
+            (node!(race_start(rc.clone()) >> branches), out_ind, race_get(rc))
+        }
+    }
+}
+
+// Im - NI
+implNI!{
+    (InP,InQ),
+    impl<'a, InP: Val<'a>, InQ: Val<'a>, OutP: Val<'a>, OutQ: Val<'a>,
+         MarkOnceP, MarkOnceQ, PNIO, QNI, QNO>
+        for Race<ProcessIm<'a, InP, OutP, MarkOnceP, PNIO>,
+                 ProcessNotIm<'a, InQ, OutQ, MarkOnceQ, QNI, QNO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNIO: Node<'a, InP, Out = OutP>,
+        QNI: Node<'a, InQ, Out = ()>,
+        QNO: Node<'a, (), Out = OutQ>,
+
+    trait IntProcessNotIm<'a, (InP,InQ)>
+    {
+        type NI = NSeq<NRaceStart<OutP, OutQ>,
+                       NSeq<NPar<NSeq<PNIO, NRaceSet1<OutP, OutQ>>, QNI>, Ignore>>;
+        type NO = NRaceGet<OutP, OutQ>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let Race(p, q) = *self;
+            let out_ind = g.reserve();
+            let rc = Rcrp::new();
+            let pnio = p.compileIm(g);
+            g.push_wrapper(box rc.clone());
+            let (qni, qind, qno) = q.compile(g);
+            g.set(qind, box node!(qno >> race2(rc.clone(), out_ind)));
+            g.pop_wrapper();
+            let pni = node!(pnio >> race1(rc.clone(), out_ind));
+            let branches = nodei!(pni || qni);
+            (node!(race_start(rc.clone()) >> branches), out_ind, race_get(rc))
+        }
+    }
+}
+
+// NI - Im
+implNI!{
+    (InP,InQ),
+    impl<'a, InP: Val<'a>, InQ: Val<'a>, OutP: Val<'a>, OutQ: Val<'a>,
+         MarkOnceP, MarkOnceQ, PNI, PNO, QNIO>
+        for Race<ProcessNotIm<'a, InP, OutP, MarkOnceP, PNI, PNO>,
+                 ProcessIm<'a, InQ, OutQ, MarkOnceQ, QNIO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNI: Node<'a, InP, Out = ()>,
+        PNO: Node<'a, (), Out = OutP>,
+        QNIO: Node<'a, InQ, Out = OutQ>,
+
+    trait IntProcessNotIm<'a, (InP,InQ)>
+    {
+        type NI = NSeq<NRaceStart<OutP, OutQ>,
+                       NSeq<NPar<PNI, NSeq<QNIO, NRaceSet2<OutP, OutQ>>>, Ignore>>;
+        type NO = NRaceGet<OutP, OutQ>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let Race(p, q) = *self;
+            let out_ind = g.reserve();
+            let rc = Rcrp::new();
+            g.push_wrapper(box rc.clone());
+            let (pni, pind, pno) = p.compile(g);
+            g.set(pind, box node!(pno >> race1(rc.clone(), out_ind)));
+            g.pop_wrapper();
+            let qnio = q.compileIm(g);
+            let qni = node!(qnio >> race2(rc.clone(), out_ind));
+            let branches = nodei!(pni || qni);
+            (node!(race_start(rc.clone()) >> branches), out_ind, race_get(rc))
+        }
+    }
+}
+
+// Im - Im
+implNI!{
+    (InP,InQ),
+    impl<'a, InP: Val<'a>, InQ: Val<'a>, OutP: Val<'a>, OutQ: Val<'a>,
+         MarkOnceP, MarkOnceQ, PNIO, QNIO>
+        for Race<ProcessIm<'a, InP, OutP, MarkOnceP, PNIO>,
+                 ProcessIm<'a, InQ, OutQ, MarkOnceQ, QNIO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNIO: Node<'a, InP, Out = OutP>,
+        QNIO: Node<'a, InQ, Out = OutQ>,
+
+    trait IntProcessNotIm<'a, (InP,InQ)>
+    {
+        type NI = NSeq<NRaceStart<OutP, OutQ>,
+                       NSeq<NPar<NSeq<PNIO, NRaceSet1<OutP, OutQ>>,
+                                 NSeq<QNIO, NRaceSet2<OutP, OutQ>>>, Ignore>>;
+        type NO = NRaceGet<OutP, OutQ>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let Race(p, q) = *self;
+            let out_ind = g.reserve();
+            let rc = Rcrp::new();
+            let pnio = p.compileIm(g);
+            let qnio = q.compileIm(g);
+            let pni = node!(pnio >> race1(rc.clone(), out_ind));
+            let qni = node!(qnio >> race2(rc.clone(), out_ind));
+            let branches = nodei!(pni || qni);
+            (node!(race_start(rc.clone()) >> branches), out_ind, race_get(rc))
+        }
+    }
+}
+
+/// Builds a `Race` between p and q, see `Process::race`.
+pub fn race<'a, InP: Val<'a>, InQ: Val<'a>, P, Q>(p: P, q: Q)
+    -> <Race<P, Q> as ToBoxedProcess<'a, (InP, InQ)>>::Boxed
+where
+    P: Process<'a, InP>,
+    Q: Process<'a, InQ>,
+    Race<P, Q>: ToBoxedProcess<'a, (InP, InQ)>,
+{
+    Race(p, q).tobox()
+}
+
+
+
+
 //  ____  _
 // | __ )(_) __ _
 // |  _ \| |/ _` |
@@ -241,9 +433,55 @@ where
             dests.push(g.add(box node!(load_copy(rcin.clone()) >> pni)));
         }
         (
-            node!(store(rcin) >> NBigPar { dests }),
+            node!(store(rcin) >> NBigPar { rc: rcbjp, dests }),
             end_point,
             Nothing {},
         )
     }
 }
+
+
+/// A process implementation that put many process in parallel, and collects their results.
+///
+/// The process i takes the i-th value of the input vector.
+/// It waits that all processes have finished before returning the vector of their results.
+pub struct BigParCollect<'a, In, Out>(pub(crate) Vec<BoxedBranch<'a, In, Out>>);
+
+impl<'a, In: Val<'a>, Out: Val<'a>> IntProcess<'a, Vec<In>> for BigParCollect<'a, In, Out> {
+    type Out = Vec<Out>;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"BigParCollect\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, In: Val<'a>, Out: Val<'a>> IntProcessNotIm<'a, Vec<In>> for BigParCollect<'a, In, Out> {
+    type NI = NBigParCollect<In, Out>;
+    type NO = NCollectGet<Out>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let mut cells = vec![];
+        let mut dests = vec![];
+        let end_point = g.reserve();
+        let rccp = Rccp::new(self.0.len());
+        for (i, p) in self.0.into_iter().enumerate() {
+            let rcin = RCell::new();
+            let pni = p.compile_branch(g, box collect(rccp.clone(), i, end_point));
+            dests.push(g.add(box node!(load(rcin.clone()) >> pni)));
+            cells.push(rcin);
+        }
+        (
+            NBigParCollect {
+                rc: rccp.clone(),
+                cells,
+                dests,
+                end: end_point,
+            },
+            end_point,
+            collect_get(rccp),
+        )
+    }
+}
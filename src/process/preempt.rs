@@ -0,0 +1,535 @@
+use node::*;
+use signal::*;
+use super::*;
+
+pub use node::Trap;
+
+//     _    _                _
+//    / \  | |__   ___  _ __| |_
+//   / _ \ | '_ \ / _ \| '__| __|
+//  / ___ \| |_) | (_) | |  | |_
+// /_/   \_\_.__/ \___/|_|   \__|
+
+/// Compiles a preemptible `body`, which runs until `signal` is emitted. It is then killed at
+/// the end of the instant, and the handler starts at the next instant.
+///
+/// `handler` compiles the handler, which must store its value in the given cell and jump to
+/// the given output id. It returns the id of the main node starting the handler.
+fn compile_preempt<'a, In, Out, MarkOnce, PNI, PNO, S, F>(
+    g: &mut Graph<'a>,
+    signal: S,
+    body: ProcessNotIm<'a, In, Out, MarkOnce, PNI, PNO>,
+    handler: F,
+) -> (NSeq<NPreemptEnter<'a, S>, PNI>, usize, NLoad<Out>)
+where
+    In: Val<'a>,
+    Out: Val<'a>,
+    PNI: Node<'a, In, Out = ()>,
+    PNO: Node<'a, (), Out = Out>,
+    S: Signal<'a>,
+    F: FnOnce(&mut Graph<'a>, RCell<Out>, usize) -> usize,
+{
+    let rcp = RCell::new();
+    let rch = rcp.clone();
+    let rcout = rcp.clone();
+    let out_id = g.reserve();
+    let handler_id = handler(g, rch, out_id);
+
+    let rc = RcPreempt::new();
+    rc.set_handler(handler_id);
+    let watch_id = g.add(box NPreemptWatch { rc: rc.clone() });
+
+    g.push_wrapper(box rc.clone());
+    let (pni, pind, pno) = body.compile(g);
+    g.set(pind, box node!(pno >> store(rcp) >> leave(rc.clone()) >> njump(out_id)));
+    g.pop_wrapper();
+
+    let enter = NPreemptEnter {
+        rc,
+        signal,
+        watch: watch_id,
+        scope: g.scope(),
+    };
+    (node!(enter >> pni), out_id, load(rcout))
+}
+
+/// Preemption (`do body until signal`): runs `body` until `signal` is emitted.
+///
+/// The body finishes the instant where the signal is emitted, and it is killed at the end of
+/// the instant. `handler` is then run at the next instant.
+/// If the body terminates before being killed, its value is returned.
+/// An immediate body terminates before it can be killed, the abort is then the body itself.
+pub struct PAbort<P, H, S> {
+    pub(crate) body: P,
+    pub(crate) handler: H,
+    pub(crate) signal: S,
+}
+
+impl<'a, P, H, S: Val<'a>, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PAbort<P, H, S>
+where
+    P: Process<'a, In, Out = Out>,
+    H: Process<'a, (), Out = Out>,
+    S: Signal<'a>,
+{
+    type Out = Out;
+    type MarkOnce = <And<P::MarkOnce, H::MarkOnce> as GiveOnce>::Once;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (begp, endp) = self.body.printDot(curNum);
+        let (begh, endh) = self.handler.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = box, label= \"Abort\"];", numbeg);
+        println!("{} -> {} [label = \"{}\"];", numbeg, begp, tname::<In>());
+        println!("{} -> {} [style = dashed, label = \"preempted\"];", numbeg, begh);
+        println!("{} [size = 0.1]", numend);
+        println!("{} -> {}", endp, numend);
+        println!("{} -> {}", endh, numend);
+        (numbeg, numend)
+    }
+}
+
+// NI - NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnceP, MarkOnceH, PNI, PNO, HNI, HNO>
+        for PAbort<ProcessNotIm<'a, In, Out, MarkOnceP, PNI, PNO>,
+                   ProcessNotIm<'a, (), Out, MarkOnceH, HNI, HNO>, S>
+        where
+        MarkOnceP: Once,
+        MarkOnceH: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+        HNI: Node<'a, (), Out = ()>,
+        HNO: Node<'a, (), Out = Out>,
+        S: Signal<'a>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NPreemptEnter<'a, S>, PNI>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let handler = s.handler;
+            compile_preempt(g, s.signal, s.body, |g, rch, out_id| {
+                let (hni, hind, hno) = handler.compile(g);
+                g.set(hind, box node!(hno >> store(rch) >> njump(out_id)));
+                g.add(box hni)
+            })
+        }
+    }
+}
+
+// NI - Im
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnceP, MarkOnceH, PNI, PNO, HNIO>
+        for PAbort<ProcessNotIm<'a, In, Out, MarkOnceP, PNI, PNO>,
+                   ProcessIm<'a, (), Out, MarkOnceH, HNIO>, S>
+        where
+        MarkOnceP: Once,
+        MarkOnceH: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+        HNIO: Node<'a, (), Out = Out>,
+        S: Signal<'a>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NPreemptEnter<'a, S>, PNI>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let handler = s.handler;
+            compile_preempt(g, s.signal, s.body, |g, rch, out_id| {
+                let hnio = handler.compileIm(g);
+                g.add(box node!(hnio >> store(rch) >> njump(out_id)))
+            })
+        }
+    }
+}
+
+// Im
+implIm!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnceP, PNIO, H>
+        for PAbort<ProcessIm<'a, In, Out, MarkOnceP, PNIO>, H, S>
+        where
+        MarkOnceP: Once,
+        PNIO: Node<'a, In, Out = Out>,
+        H: Process<'a, (), Out = Out>,
+        S: Signal<'a>,
+
+    trait IntProcessIm<'a, In>
+    {
+        type NIO = PNIO;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO {
+            // the body terminates in the instant it starts, before it can be killed
+            let s = *self;
+            s.body.compileIm(g)
+        }
+    }
+}
+
+
+// __        ___
+// \ \      / / |__   ___ _ __
+//  \ \ /\ / /| '_ \ / _ \ '_ \
+//   \ V  V / | | | |  __/ | | |
+//    \_/\_/  |_| |_|\___|_| |_|
+
+/// Suspension (`do body when signal`): the body only executes in instants where
+/// the signal is present, and is frozen in the other instants.
+pub struct PWhen<P, S> {
+    pub(crate) body: P,
+    pub(crate) signal: S,
+}
+
+impl<'a, P, S: Val<'a>, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PWhen<P, S>
+where
+    P: Process<'a, In, Out = Out>,
+    S: Signal<'a>,
+{
+    type Out = Out;
+    type MarkOnce = P::MarkOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (beg, end) = self.body.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = box, label= \"When\"];", numbeg);
+        println!("{} -> {} [label = \"{}\"];", numbeg, beg, tname::<In>());
+        println!("{} [size = 0.1]", numend);
+        println!("{} -> {}", end, numend);
+        (numbeg, numend)
+    }
+}
+
+// NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnce, PNI, PNO>
+        for PWhen<ProcessNotIm<'a, In, Out, MarkOnce, PNI, PNO>, S>
+        where
+        MarkOnce: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+        S: Signal<'a> + Clone,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NStore<In>, NJump>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let rcextin = RCell::new();
+            let rcin = rcextin.clone();
+            let rcout = RCell::new();
+            let rcextout = rcout.clone();
+            let out_id = g.reserve();
+
+            g.push_wrapper(box WhenGuard(s.signal));
+            let (pni, pind, pno) = s.body.compile(g);
+            g.set(pind, box node!(pno >> store(rcout) >> njump(out_id)));
+            let in_id = g.add(box node!(load(rcin) >> pni));
+            g.pop_wrapper();
+
+            (node!(store(rcextin) >> njump(in_id)), out_id, load(rcextout))
+        }
+    }
+}
+
+// Im
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnce, PNIO>
+        for PWhen<ProcessIm<'a, In, Out, MarkOnce, PNIO>, S>
+        where
+        MarkOnce: Once,
+        PNIO: Node<'a, In, Out = Out>,
+        S: Signal<'a> + Clone,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NStore<In>, NJump>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let rcextin = RCell::new();
+            let rcin = rcextin.clone();
+            let rcout = RCell::new();
+            let rcextout = rcout.clone();
+            let out_id = g.reserve();
+
+            g.push_wrapper(box WhenGuard(s.signal));
+            let pnio = s.body.compileIm(g);
+            let in_id = g.add(box node!(load(rcin) >> pnio >> store(rcout) >> njump(out_id)));
+            g.pop_wrapper();
+
+            (node!(store(rcextin) >> njump(in_id)), out_id, load(rcextout))
+        }
+    }
+}
+
+
+//  _   _       _   _ _
+// | | | |_ __ | |_(_) |
+// | | | | '_ \| __| | |
+// | |_| | | | | |_| | |
+//  \___/|_| |_|\__|_|_|
+
+/// Weak preemption (`do body until signal(v) -> handler`): runs `body` until `signal` is emitted.
+///
+/// When the signal is emitted, the body finishes its current instant, and it is killed at the
+/// end of the instant. `handler` is then run at the next instant, with the value gathered by
+/// the signal and the signal itself.
+/// If the body terminates before being killed, its value is returned.
+/// An immediate body terminates before it can be killed, the until is then the body itself.
+pub struct PUntil<P, H, S> {
+    pub(crate) body: P,
+    pub(crate) handler: H,
+    pub(crate) signal: S,
+}
+
+impl<'a, P, H, S: Val<'a>, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PUntil<P, H, S>
+where
+    P: Process<'a, In, Out = Out>,
+    H: Process<'a, (S::V, S), Out = Out>,
+    S: Signal<'a>,
+{
+    type Out = Out;
+    type MarkOnce = <And<P::MarkOnce, H::MarkOnce> as GiveOnce>::Once;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (begp, endp) = self.body.printDot(curNum);
+        let (begh, endh) = self.handler.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = box, label= \"Until\"];", numbeg);
+        println!("{} -> {} [label = \"{}\"];", numbeg, begp, tname::<In>());
+        println!("{} -> {} [style = dashed, label = \"{}\"];", numbeg, begh, tname::<(S::V, S)>());
+        println!("{} [size = 0.1]", numend);
+        println!("{} -> {}", endp, numend);
+        println!("{} -> {}", endh, numend);
+        (numbeg, numend)
+    }
+}
+
+// NI - NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, V: Val<'a>, MarkOnceP, MarkOnceH, PNI, PNO, HNI, HNO>
+        for PUntil<ProcessNotIm<'a, In, Out, MarkOnceP, PNI, PNO>,
+                   ProcessNotIm<'a, (V, S), Out, MarkOnceH, HNI, HNO>, S>
+        where
+        MarkOnceP: Once,
+        MarkOnceH: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+        HNI: Node<'a, (V, S), Out = ()>,
+        HNO: Node<'a, (), Out = Out>,
+        S: Signal<'a, V = V> + Clone,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NPreemptEnter<'a, S>, PNI>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let handler = s.handler;
+            let signal = s.signal.clone();
+            compile_preempt(g, s.signal, s.body, |g, rch, out_id| {
+                let (hni, hind, hno) = handler.compile(g);
+                g.set(hind, box node!(hno >> store(rch) >> njump(out_id)));
+                g.add(box node!(NValue(signal.clone()) >> NGetSIn(signal) >> hni))
+            })
+        }
+    }
+}
+
+// NI - Im
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, V: Val<'a>, MarkOnceP, MarkOnceH, PNI, PNO, HNIO>
+        for PUntil<ProcessNotIm<'a, In, Out, MarkOnceP, PNI, PNO>,
+                   ProcessIm<'a, (V, S), Out, MarkOnceH, HNIO>, S>
+        where
+        MarkOnceP: Once,
+        MarkOnceH: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+        HNIO: Node<'a, (V, S), Out = Out>,
+        S: Signal<'a, V = V> + Clone,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NPreemptEnter<'a, S>, PNI>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let handler = s.handler;
+            let signal = s.signal.clone();
+            compile_preempt(g, s.signal, s.body, |g, rch, out_id| {
+                let hnio = handler.compileIm(g);
+                g.add(box node!(
+                    NValue(signal.clone()) >> NGetSIn(signal) >> hnio >> store(rch) >> njump(out_id)))
+            })
+        }
+    }
+}
+
+// Im
+implIm!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, S: Val<'a>, MarkOnceP, PNIO, H>
+        for PUntil<ProcessIm<'a, In, Out, MarkOnceP, PNIO>, H, S>
+        where
+        MarkOnceP: Once,
+        PNIO: Node<'a, In, Out = Out>,
+        H: Process<'a, (S::V, S), Out = Out>,
+        S: Signal<'a>,
+
+    trait IntProcessIm<'a, In>
+    {
+        type NIO = PNIO;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO {
+            // the body terminates in the instant it starts, before it can be killed
+            let s = *self;
+            s.body.compileIm(g)
+        }
+    }
+}
+
+
+//  _____
+// |_   _| __ __ _ _ __
+//   | || '__/ _` | '_ \
+//   | || | | (_| | |_) |
+//   |_||_|  \__,_| .__/
+//                |_|
+
+/// Trap (`trap T in body`): runs `body`, which can be exited from any of its branches,
+/// however deep in parallel constructs, by an `exit` process on the same `Trap`.
+///
+/// When the trap is exited, the body finishes its instant and is killed at the end of the
+/// instant, then the value given to `exit` is returned at the next instant.
+/// If the body terminates without exit, its value is returned.
+/// An immediate body cannot exit the trap, the trap is then the body itself.
+pub struct PTrap<P, V> {
+    pub(crate) body: P,
+    pub(crate) trap: Trap<V>,
+}
+
+impl<'a, P, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In> for PTrap<P, Out>
+where
+    P: Process<'a, In, Out = Out>,
+{
+    type Out = Out;
+    type MarkOnce = P::MarkOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (beg, end) = self.body.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = box, label= \"Trap\"];", numbeg);
+        println!("{} -> {} [label = \"{}\"];", numbeg, beg, tname::<In>());
+        println!("{} [size = 0.1]", numend);
+        println!("{} -> {}", end, numend);
+        println!("{} -> {} [style = dashed, label = \"exit\"];", numbeg, numend);
+        (numbeg, numend)
+    }
+}
+
+// NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, MarkOnce, PNI, PNO>
+        for PTrap<ProcessNotIm<'a, In, Out, MarkOnce, PNI, PNO>, Out>
+        where
+        MarkOnce: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NTrapEnter, PNI>;
+        type NO = NLoad<Out>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let trap = s.trap;
+            let out_id = g.reserve();
+            trap.rc.set_handler(out_id);
+
+            g.push_wrapper(box trap.rc.clone());
+            let (pni, pind, pno) = s.body.compile(g);
+            g.set(pind, box node!(pno >> NTrapLeave { trap: trap.clone(), dest: out_id }));
+            g.pop_wrapper();
+
+            let enter = NTrapEnter { rc: trap.rc.clone() };
+            (node!(enter >> pni), out_id, load(trap.val))
+        }
+    }
+}
+
+// Im
+implIm!{
+    In,
+    impl<'a, In: Val<'a>, Out: Val<'a>, MarkOnce, PNIO>
+        for PTrap<ProcessIm<'a, In, Out, MarkOnce, PNIO>, Out>
+        where
+        MarkOnce: Once,
+        PNIO: Node<'a, In, Out = Out>,
+
+    trait IntProcessIm<'a, In>
+    {
+        type NIO = PNIO;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO {
+            // an exit is not immediate, so the body cannot exit the trap
+            let s = *self;
+            s.body.compileIm(g)
+        }
+    }
+}
+
+
+//  _____      _ _
+// | ____|_  _(_) |_
+// |  _| \ \/ / | __|
+// | |___ >  <| | |_
+// |_____/_/\_\_|\__|
+
+/// Exits a trap with the input value (see `PTrap`). It never terminates.
+pub struct PExit<V, Out>(pub(crate) Trap<V>, PhantomData<Out>);
+
+/// Builds a `PExit`, it can have any output type since it never terminates.
+/// this is equivalent to `exit T(v)` in Esterel.
+pub fn exit<'a, V: Val<'a>, Out: Val<'a>>(trap: Trap<V>) -> ProcessNotIm<'a, V, Out, NotOnce, NExit<V>, NNever<Out>> {
+    ProcessNotIm(box PExit(trap, PhantomData))
+}
+
+impl<'a, V: Val<'a>, Out: Val<'a>> IntProcess<'a, V> for PExit<V, Out> {
+    type Out = Out;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let num = *curNum;
+        *curNum += 1;
+        println!("{} [shape = box, label= \"Exit\"];", num);
+        (num, num)
+    }
+}
+
+impl<'a, V: Val<'a>, Out: Val<'a>> IntProcessNotIm<'a, V> for PExit<V, Out> {
+    type NI = NExit<V>;
+    type NO = NNever<Out>;
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let PExit(trap, _) = *self;
+        // the end of the process is never scheduled.
+        let out = g.reserve();
+        (NExit { trap }, out, nnever())
+    }
+}
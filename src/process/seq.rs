@@ -121,3 +121,147 @@ implIm!{
         }
     }
 }
+
+
+/// A process implementation representing the sequence of two processes that return `Result`:
+/// if the first one returns `Err(e)`, the second one is not run at all, its pauses and awaits
+/// included, and the whole process immediately returns `Err(e)`.
+pub struct SeqTry<P, Q>(pub(crate) P, pub(crate) Q);
+
+impl<'a, P, Q, In: Val<'a>, T: Val<'a>, U: Val<'a>, E: Val<'a>> IntProcess<'a, In> for SeqTry<P,Q>
+    where
+    P: Process<'a, In, Out = Result<T, E>>,
+    Q: Process<'a, T, Out = Result<U, E>>,
+{
+    type Out = Result<U, E>;
+    type MarkOnce = <And<P::MarkOnce, Q::MarkOnce> as GiveOnce>::Once;
+
+    fn printDot(&mut self,curNum : &mut usize) -> (usize,usize){
+        let (beg,midup) = self.0.printDot(curNum);
+        let (middown,end) = self.1.printDot(curNum);
+        println!("{} -> {} [label = \"Ok:{}\"];",midup,middown,tname::<T>());
+        println!("{} -> {} [label = \"Err:{}\"];",midup,end,tname::<E>());
+        (beg,end)
+    }
+}
+
+// NI - NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, T: Val<'a>, U: Val<'a>, E: Val<'a>, MarkOnceP, MarkOnceQ, PNI, PNO, QNI, QNO>
+        for SeqTry<ProcessNotIm<'a, In, Result<T, E>, MarkOnceP, PNI, PNO>,
+                   ProcessNotIm<'a, T, Result<U, E>, MarkOnceQ, QNI, QNO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Result<T, E>>,
+        QNI: Node<'a, T, Out = ()>,
+        QNO: Node<'a, (), Out = Result<U, E>>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = PNI;
+        type NO = NLoad<Result<U, E>>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let SeqTry(p, q) = s;
+            let (pni, pind, pno) = p.compile(g);
+            let (qni, qind, qno) = q.compile(g);
+            let rcq = RCell::new();
+            let rce = rcq.clone();
+            let rcout = rcq.clone();
+            let out = g.reserve();
+            g.set(pind, box node!(pno >> ntry() >> choice qni {nerr() >> store(rce) >> njump(out)}));
+            g.set(qind, box node!(qno >> store(rcq) >> njump(out)));
+            (pni, out, load(rcout))
+        }
+    }
+}
+
+// Im - NI
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, T: Val<'a>, U: Val<'a>, E: Val<'a>, MarkOnceP, MarkOnceQ, PNIO, QNI, QNO>
+        for SeqTry<ProcessIm<'a, In, Result<T, E>, MarkOnceP, PNIO>,
+                   ProcessNotIm<'a, T, Result<U, E>, MarkOnceQ, QNI, QNO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNIO: Node<'a, In, Out = Result<T, E>>,
+        QNI: Node<'a, T, Out = ()>,
+        QNO: Node<'a, (), Out = Result<U, E>>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<PNIO, NSeq<NTry, NChoice<QNI, NSeq<NErr<U>, NSeq<NStore<Result<U, E>>, NJump>>>>>;
+        type NO = NLoad<Result<U, E>>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let SeqTry(p, q) = s;
+            let pnio = p.compileIm(g);
+            let (qni, qind, qno) = q.compile(g);
+            let rcq = RCell::new();
+            let rce = rcq.clone();
+            let rcout = rcq.clone();
+            let out = g.reserve();
+            g.set(qind, box node!(qno >> store(rcq) >> njump(out)));
+            (
+                node!(pnio >> ntry() >> choice qni {nerr() >> store(rce) >> njump(out)}),
+                out,
+                load(rcout),
+            )
+        }
+    }
+}
+
+// NI - Im
+implNI!{
+    In,
+    impl<'a, In: Val<'a>, T: Val<'a>, U: Val<'a>, E: Val<'a>, MarkOnceP, MarkOnceQ, PNI, PNO, QNIO>
+        for SeqTry<ProcessNotIm<'a, In, Result<T, E>, MarkOnceP, PNI, PNO>,
+                   ProcessIm<'a, T, Result<U, E>, MarkOnceQ, QNIO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNI: Node<'a, In, Out = ()>,
+        PNO: Node<'a, (), Out = Result<T, E>>,
+        QNIO: Node<'a, T, Out = Result<U, E>>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = PNI;
+        type NO = NSeq<PNO, NSeq<NTry, NChoice<QNIO, NErr<U>>>>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            let SeqTry(p, q) = s;
+            let (pni, pind, pno) = p.compile(g);
+            let qnio = q.compileIm(g);
+            (pni, pind, node!(pno >> ntry() >> choice qnio {nerr()}))
+        }
+    }
+}
+
+// Im - Im
+implIm!{
+    In,
+    impl<'a, In: Val<'a>, T: Val<'a>, U: Val<'a>, E: Val<'a>, MarkOnceP, MarkOnceQ, PNIO, QNIO>
+        for SeqTry<ProcessIm<'a, In, Result<T, E>, MarkOnceP, PNIO>,
+                   ProcessIm<'a, T, Result<U, E>, MarkOnceQ, QNIO>>
+        where
+        MarkOnceP: Once,
+        MarkOnceQ: Once,
+        PNIO: Node<'a, In, Out = Result<T, E>>,
+        QNIO: Node<'a, T, Out = Result<U, E>>,
+    trait IntProcessIm<'a, In>
+    {
+        type NIO = NSeq<PNIO, NSeq<NTry, NChoice<QNIO, NErr<U>>>>;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO {
+            let s = *self;
+            let SeqTry(p, q) = s;
+            let pnio = p.compileIm(g);
+            let qnio = q.compileIm(g);
+            node!(pnio >> ntry() >> choice qnio {nerr()})
+        }
+    }
+}
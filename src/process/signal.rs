@@ -821,6 +821,76 @@ where
 }
 
 
+/// Process awaiting an emission of a signal whose value satisfies a predicate,
+/// and returning this value at the instant following the emission.
+///
+/// The predicate is checked once per emission, on the value gathered during the instant
+/// of the emission. The input value is passed through: the signature is In -> (V, In).
+///
+/// The value is read before the predicate is checked, even if it is rejected. It must not be
+/// used with the signals whose value is moved to its reader instead of being cloned:
+/// single consumer signals (`SignalRuntimeRef::new_sc`) and spawn signals
+/// (`SignalRuntimeRef::new_spawn`), as a rejected value would be lost for the other readers.
+pub struct AwaitSInWhen<S, F>(pub S, pub F);
+
+
+impl<'a, In: Val<'a>, S: Val<'a>, F: Val<'a>> IntProcess<'a, In> for AwaitSInWhen<S, F>
+where
+    S: Signal<'a>,
+    F: FnMut(&S::V) -> bool,
+{
+    type Out = (S::V, In);
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self,curNum : &mut usize) -> (usize,usize){
+        let num = *curNum;
+        *curNum +=1;
+        println!("{} [shape = box, label= \"AwaitSWhen\"];",num);
+        (num,num)
+    }
+}
+
+impl<'a, In: Val<'a>, S: Val<'a>, F: Val<'a>> IntProcessNotIm<'a, In> for AwaitSInWhen<S, F>
+where
+    S: Signal<'a> + Clone,
+    F: FnMut(&S::V) -> bool,
+{
+    type NI = NSeq<NStore<In>,NAwaitS<S>>;
+    type NO = NSeq<GenP, NPar<NLoad<S::V>, NLoad<In>>>;
+
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let s = *self;
+        let out_id = g.reserve();
+        let rc = RCell::new();
+        let rc2 = rc.clone();
+        let rcv = RCell::new();
+        let rcv2 = rcv.clone();
+
+        let check_id = g.reserve();
+        g.set(check_id, box NAwaitSWhen {
+            signal: s.0.clone(),
+            pred: s.1,
+            rc: rcv,
+            id: check_id,
+            dest: out_id,
+        });
+        let ni = node!(store(rc) >> NAwaitS(s.0, check_id));
+        let no = node!( GenP{} >> (load(rcv2) || load(rc2)));
+        (ni, out_id, no)
+    }
+}
+
+/// Builds an `AwaitSInWhen`, it must not be used on single consumer or spawn signals.
+pub fn await_s_in_when<'a, In: Val<'a>, S: Val<'a>, F: Val<'a>>(signal_runtime: S, pred: F)
+    -> ProcessNotIm<'a, In, (S::V, In), NotOnce, NSeq<NStore<In>,NAwaitS<S>>, NSeq<GenP, NPar<NLoad<S::V>, NLoad<In>>>>
+where
+    S: Signal<'a> + Clone,
+    F: FnMut(&S::V) -> bool,
+{
+    ProcessNotIm(box AwaitSInWhen(signal_runtime, pred))
+}
+
+
 
 //     _                _ _   ___                              _ _       _       ____
 //    / \__      ____ _(_) |_|_ _|_ __ ___  _ __ ___   ___  __| (_) __ _| |_ ___|  _ \
@@ -1375,3 +1445,401 @@ implNI! {
     }
 }
 
+
+
+//  ____                           _   _____
+// |  _ \ _ __ ___  ___  ___ _ __ | |_| ____|_  ___ __  _ __
+// | |_) | '__/ _ \/ __|/ _ \ '_ \| __|  _| \ \/ / '_ \| '__|
+// |  __/| | |  __/\__ \  __/ | | | |_| |___ >  <| |_) | |
+// |_|   |_|  \___||___/\___|_| |_|\__|_____/_/\_\ .__/|_|
+//                                               |_|
+
+/// Process that executes pt in the current instant if the signal expression holds this instant,
+/// and executes pf in the next instant otherwise.
+///
+/// The expression is decided as soon as its value is known from the signals emitted so far.
+/// Otherwise it is decided at the end of the instant, and then both branches start at the
+/// next instant.
+pub struct PresentExpr<'a, PT, PF> {
+    pub(crate) pt: PT,
+    pub(crate) pf: PF,
+    pub(crate) expr: SignalExpr<'a>,
+}
+
+impl<'a, PT, PF, Out: Val<'a>> IntProcess<'a, ()> for PresentExpr<'a, PT, PF>
+where
+    PT: Process<'a, (), Out = Out>,
+    PF: Process<'a, (), Out = Out>,
+{
+    type Out = Out;
+    type MarkOnce = <And<PT::MarkOnce, PF::MarkOnce> as GiveOnce>::Once;
+
+    fn printDot(&mut self,curNum : &mut usize) -> (usize,usize){
+        let num = *curNum;
+        *curNum +=1;
+        println!("{} [shape = box, label= \"PresentExpr\"];",num);
+        (num,num)
+    }
+}
+
+/// Builds the entry node of a `PresentExpr`, and its watching node.
+fn present_expr_node<'a>(
+    g: &mut Graph<'a>,
+    expr: SignalExpr<'a>,
+    node_true: usize,
+    node_false: usize,
+) -> NPresentExpr<'a> {
+    let rc = RcExprWait::new(expr, g.scope());
+    let watch = g.add(box NPresentExprWatch {
+        rc: rc.clone(),
+        node_true,
+        node_false,
+    });
+    NPresentExpr {
+        rc,
+        watch,
+        node_true,
+        node_false,
+    }
+}
+
+// NI - NI
+implNI! {
+    (),
+    impl<'a, Out: Val<'a>, MarkOnceT, MarkOnceF, PTNI, PTNO, PFNI, PFNO>
+        for PresentExpr<'a, ProcessNotIm<'a, (), Out, MarkOnceT, PTNI, PTNO>, ProcessNotIm<'a, (), Out, MarkOnceF, PFNI, PFNO>>
+        where
+        MarkOnceT: Once,
+        MarkOnceF: Once,
+        PTNI: Node<'a, (), Out = ()>,
+        PTNO: Node<'a, (), Out = Out>,
+        PFNI: Node<'a, (), Out = ()>,
+        PFNO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, ()>
+    {
+         type NI = NPresentExpr<'a>;
+         type NO = NLoad<Out>;
+
+         fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let rct = RCell::new();
+            let rcf = rct.clone();
+            let rc_out = rct.clone();
+
+            let s = *self;
+            let (ptni, ptind, ptno) = s.pt.0.compile(g);
+            let (pfni, pfind, pfno) = s.pf.0.compile(g);
+
+            let out_id = g.reserve();
+            g.set(ptind, box node!(ptno >> store(rct) >> njump(out_id)));
+            g.set(pfind, box node!(pfno >> store(rcf) >> njump(out_id)));
+            let nit_id = g.add(box ptni);
+            let nif_id = g.add(box pfni);
+
+            let ni = present_expr_node(g, s.expr, nit_id, nif_id);
+            (ni, out_id, load(rc_out))
+        }
+    }
+}
+
+// Im - NI
+implNI! {
+    (),
+    impl<'a, Out: Val<'a>, MarkOnceT, MarkOnceF, PTNIO, PFNI, PFNO>
+        for PresentExpr<'a, ProcessIm<'a, (), Out, MarkOnceT, PTNIO>, ProcessNotIm<'a, (), Out, MarkOnceF, PFNI, PFNO>>
+        where
+        MarkOnceT: Once,
+        MarkOnceF: Once,
+        PTNIO: Node<'a, (), Out = Out>,
+        PFNI: Node<'a, (), Out = ()>,
+        PFNO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, ()>
+    {
+         type NI = NPresentExpr<'a>;
+         type NO = NLoad<Out>;
+
+         fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let rct = RCell::new();
+            let rcf = rct.clone();
+            let rc_out = rct.clone();
+
+            let s = *self;
+            let ptnio = s.pt.0.compileIm(g);
+            let (pfni, pfind, pfno) = s.pf.0.compile(g);
+
+            let out_id = g.reserve();
+            let ptind = g.add(box node!(ptnio >> store(rct) >> njump(out_id)));
+            g.set(pfind, box node!(pfno >> store(rcf) >> njump(out_id)));
+            let nif_id = g.add(box pfni);
+
+            let ni = present_expr_node(g, s.expr, ptind, nif_id);
+            (ni, out_id, load(rc_out))
+        }
+    }
+}
+
+// NI - Im
+implNI! {
+    (),
+    impl<'a, Out: Val<'a>, MarkOnceT, MarkOnceF, PTNI, PTNO, PFNIO>
+        for PresentExpr<'a, ProcessNotIm<'a, (), Out, MarkOnceT, PTNI, PTNO>, ProcessIm<'a, (), Out, MarkOnceF, PFNIO>>
+        where
+        MarkOnceT: Once,
+        MarkOnceF: Once,
+        PFNIO: Node<'a, (), Out = Out>,
+        PTNI: Node<'a, (), Out = ()>,
+        PTNO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, ()>
+    {
+         type NI = NPresentExpr<'a>;
+         type NO = NLoad<Out>;
+
+         fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let rct = RCell::new();
+            let rcf = rct.clone();
+            let rc_out = rct.clone();
+
+            let s = *self;
+            let pfnio = s.pf.0.compileIm(g);
+            let (ptni, ptind, ptno) = s.pt.0.compile(g);
+
+            let out_id = g.reserve();
+            let pfind = g.add(box node!(pfnio >> store(rcf) >> njump(out_id)));
+            g.set(ptind, box node!(ptno >> store(rct) >> njump(out_id)));
+            let nit_id = g.add(box ptni);
+
+            let ni = present_expr_node(g, s.expr, nit_id, pfind);
+            (ni, out_id, load(rc_out))
+        }
+    }
+}
+
+// Im - Im
+implNI! {
+    (),
+    impl<'a, Out: Val<'a>, MarkOnceT, MarkOnceF, PTNIO, PFNIO>
+        for PresentExpr<'a, ProcessIm<'a, (), Out, MarkOnceT, PTNIO>, ProcessIm<'a, (), Out, MarkOnceF, PFNIO>>
+        where
+        MarkOnceT: Once,
+        MarkOnceF: Once,
+        PFNIO: Node<'a, (), Out = Out>,
+        PTNIO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, ()>
+    {
+         type NI = NPresentExpr<'a>;
+         type NO = NLoad<Out>;
+
+         fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let rct = RCell::new();
+            let rcf = rct.clone();
+            let rc_out = rct.clone();
+
+            let s = *self;
+            let pfnio = s.pf.0.compileIm(g);
+            let ptnio = s.pt.0.compileIm(g);
+
+            let out_id = g.reserve();
+            let pfind = g.add(box node!(pfnio >> store(rcf) >> njump(out_id)));
+            let ptind = g.add(box node!(ptnio >> store(rct) >> njump(out_id)));
+
+            let ni = present_expr_node(g, s.expr, ptind, pfind);
+            (ni, out_id, load(rc_out))
+        }
+    }
+}
+
+
+//     _                _ _   _____
+//    / \__      ____ _(_) |_| ____|_  ___ __  _ __
+//   / _ \ \ /\ / / _` | | __|  _| \ \/ / '_ \| '__|
+//  / ___ \ V  V / (_| | | |_| |___ >  <| |_) | |
+// /_/   \_\_/\_/ \__,_|_|\__|_____/_/\_\ .__/|_|
+//                                      |_|
+
+/// Process awaiting a signal expression to hold, and executing the next process
+/// the next instant.
+/// The input value is passed through.
+///
+/// An expression holding when no signal is emitted (like `!s`) is checked at every instant.
+pub struct AwaitExpr<'a>(pub SignalExpr<'a>);
+
+impl<'a, In: Val<'a>> IntProcess<'a, In> for AwaitExpr<'a> {
+    type Out = In;
+    type MarkOnce = NotOnce;
+
+    fn printDot(&mut self,curNum : &mut usize) -> (usize,usize){
+        let num = *curNum;
+        *curNum +=1;
+        println!("{} [shape = box, label= \"AwaitExpr\"];",num);
+        (num,num)
+    }
+}
+
+impl<'a, In: Val<'a>> IntProcessNotIm<'a, In> for AwaitExpr<'a> {
+    type NI = NSeq<NStore<In>, NAwaitExpr<'a>>;
+    type NO = NLoad<In>;
+
+    fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+        let out_id = g.reserve();
+        let rc = RCell::new();
+        let rc2 = rc.clone();
+        let rce = RcExprWait::new(self.0, g.scope());
+
+        let watch = g.reserve();
+        g.set(watch, box NAwaitExprWatch {
+            rc: rce.clone(),
+            id: watch,
+            dest: out_id,
+        });
+        let ni = node!(store(rc) >> NAwaitExpr { rc: rce, watch });
+        (ni, out_id, load(rc2))
+    }
+}
+
+pub fn await_expr<'a, In: Val<'a>>(expr: SignalExpr<'a>)
+    -> ProcessNotIm<'a, In, In, NotOnce, NSeq<NStore<In>, NAwaitExpr<'a>>, NLoad<In>>
+{
+    ProcessNotIm(box AwaitExpr(expr))
+}
+
+/// Awaits the emission of any of the signals, see `AwaitExpr`.
+pub fn await_any<'a, In: Val<'a>, S, I>(signals: I)
+    -> ProcessNotIm<'a, In, In, NotOnce, NSeq<NStore<In>, NAwaitExpr<'a>>, NLoad<In>>
+where
+    S: PureSignal<'a>,
+    I: IntoIterator<Item = S>,
+{
+    await_expr(SignalExpr::any(signals))
+}
+
+/// Awaits an instant where all the signals are emitted, see `AwaitExpr`.
+pub fn await_all<'a, In: Val<'a>, S, I>(signals: I)
+    -> ProcessNotIm<'a, In, In, NotOnce, NSeq<NStore<In>, NAwaitExpr<'a>>, NLoad<In>>
+where
+    S: PureSignal<'a>,
+    I: IntoIterator<Item = S>,
+{
+    await_expr(SignalExpr::all(signals))
+}
+
+
+//  _                    _ ____  _                   _
+// | |    ___   ___ __ _| / ___|(_) __ _ _ __   __ _| |
+// | |   / _ \ / __/ _` | \___ \| |/ _` | '_ \ / _` | |
+// | |__| (_) | (_| (_| | |___) | | (_| | | | | (_| | |
+// |_____\___/ \___\__,_|_|____/|_|\__, |_| |_|\__,_|_|
+//                                 |___/
+
+/// Process implementation declaring a local signal (`signal s in body`).
+///
+/// Each time the process is entered, a fresh signal is built by `factory` and put in the
+/// `signal` handle, which is given to the body with the input value. The signal is dropped
+/// when the body terminates, or when it is killed by an enclosing preemption.
+/// The signal type must be `'static`, so that the preemption can drop it.
+///
+/// Signature is In -> Out when body : (LocalSignal<S>,In) -> Out.
+pub struct PLocalSignal<F, S, P> {
+    pub(crate) factory: F,
+    pub(crate) signal: LocalSignal<S>,
+    pub(crate) body: P,
+}
+
+impl<'a, F: Val<'a>, S: Val<'a> + Clone, P, In: Val<'a>, Out: Val<'a>> IntProcess<'a, In>
+    for PLocalSignal<F, S, P>
+where
+    F: FnMut() -> S,
+    P: Process<'a, (LocalSignal<S>, In), Out = Out>,
+{
+    type Out = Out;
+    type MarkOnce = P::MarkOnce;
+
+    fn printDot(&mut self, curNum: &mut usize) -> (usize, usize) {
+        let (beg, end) = self.body.printDot(curNum);
+        let numbeg = *curNum;
+        let numend = numbeg + 1;
+        *curNum += 2;
+        println!("{} [shape = box, label= \"Signal {}\"];", numbeg, tname::<S>());
+        println!("{} -> {} [label = \"{}\"];", numbeg, beg, tname::<In>());
+        println!("{} [size = 0.1]", numend);
+        println!("{} -> {}", end, numend);
+        (numbeg, numend)
+    }
+}
+
+// NI
+implNI!{
+    In,
+    impl<'a, F: Val<'a>, S: Val<'a>, In: Val<'a>, Out: Val<'a>, MarkOnce, PNI, PNO>
+        for PLocalSignal<F, S, ProcessNotIm<'a, (LocalSignal<S>, In), Out, MarkOnce, PNI, PNO>>
+        where
+        F: FnMut() -> S,
+        S: Clone + 'static,
+        MarkOnce: Once,
+        PNI: Node<'a, (LocalSignal<S>, In), Out = ()>,
+        PNO: Node<'a, (), Out = Out>,
+
+    trait IntProcessNotIm<'a, In>
+    {
+        type NI = NSeq<NLocalSignalEnter<F, S>, PNI>;
+        type NO = NSeq<PNO, NLocalSignalLeave<S>>;
+        fn compile(self: Box<Self>, g: &mut Graph<'a>) -> (Self::NI, usize, Self::NO) {
+            let s = *self;
+            // the body may be killed by an enclosing preemption before reaching the leave node
+            let signal = s.signal.clone();
+            g.on_kill(|| signal.clear_hook());
+            let (pni, pind, pno) = s.body.compile(g);
+            let enter = NLocalSignalEnter {
+                factory: s.factory,
+                signal: s.signal.clone(),
+            };
+            let leave = NLocalSignalLeave { signal: s.signal };
+            (node!(enter >> pni), pind, node!(pno >> leave))
+        }
+    }
+}
+
+// Im
+implIm!{
+    In,
+    impl<'a, F: Val<'a>, S: Val<'a>, In: Val<'a>, Out: Val<'a>, MarkOnce, PNIO>
+        for PLocalSignal<F, S, ProcessIm<'a, (LocalSignal<S>, In), Out, MarkOnce, PNIO>>
+        where
+        F: FnMut() -> S,
+        S: Clone + 'static,
+        MarkOnce: Once,
+        PNIO: Node<'a, (LocalSignal<S>, In), Out = Out>,
+
+    trait IntProcessIm<'a, In>
+    {
+        type NIO = NSeq<NLocalSignalEnter<F, S>, NSeq<PNIO, NLocalSignalLeave<S>>>;
+        fn compileIm(self: Box<Self>, g: &mut Graph<'a>) -> Self::NIO {
+            let s = *self;
+            let pnio = s.body.compileIm(g);
+            let enter = NLocalSignalEnter {
+                factory: s.factory,
+                signal: s.signal.clone(),
+            };
+            let leave = NLocalSignalLeave { signal: s.signal };
+            node!(enter >> pnio >> leave)
+        }
+    }
+}
+
+/// Builds a `PLocalSignal`, where `signal` is the handle used by the body.
+/// this is equivalent to pro!{signal s in {body}}, with the handle in `s`.
+pub fn local_signal<'a, F, S, P, In: Val<'a>>(signal: LocalSignal<S>, factory: F, body: P)
+    -> <PLocalSignal<F, S, P> as ToBoxedProcess<'a, In>>::Boxed
+where
+    F: FnMut() -> S,
+    P: Process<'a, (LocalSignal<S>, In)>,
+    PLocalSignal<F, S, P>: ToBoxedProcess<'a, In>,
+{
+    (PLocalSignal {
+        factory,
+        signal,
+        body,
+    }).tobox()
+}
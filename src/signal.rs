@@ -1,8 +1,15 @@
 // use std::rc::Rc;
 // use std::cell::{RefCell, RefMut};
 use std::mem;
+use std::cell::RefCell;
+use std::cmp;
+use std::iter::{self, Sum, Product};
+use std::ops::{AddAssign, MulAssign};
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
 use utility::take;
-use engine::{SubRuntime, EndOfInstantCallback};
+use engine::{SubRuntime, EndOfInstantCallback, Task};
+use process::BoxedSpawn;
 use super::*;
 
 //  ____  _                   _ ____              _   _
@@ -24,14 +31,14 @@ pub(crate) struct SignalRuntime<SV> {
     /// The last instant where the values were updated
     pub(crate) last_update: usize,
 
-    /// Contains the ids of the nodes that await the signal
-    pub(crate) pending_await: Vec<usize>,
+    /// Contains the tasks of the nodes that await the signal
+    pub(crate) pending_await: Vec<Task>,
 
-    /// Contains the ids of the nodes that await_immediate the signal
-    pub(crate) pending_await_immediate: Vec<usize>,
+    /// Contains the tasks of the nodes that await_immediate the signal
+    pub(crate) pending_await_immediate: Vec<Task>,
 
-    /// Contains the ids of the nodes that present the signal
-    pub(crate) pending_present: Vec<(usize, usize)>,
+    /// Contains the tasks of the nodes that present the signal
+    pub(crate) pending_present: Vec<(Task, Task)>,
 
     /// Contains the values of the signal
     pub(crate) values: SV,
@@ -49,14 +56,14 @@ pub(crate) struct SignalRuntime<SV> {
     /// The last instant where the values were updated
     pub(crate) last_update: usize,
 
-    /// Contains the ids of the nodes that await the signal
-    pub(crate) pending_await: Vec<usize>,
+    /// Contains the tasks of the nodes that await the signal
+    pub(crate) pending_await: Vec<Task>,
 
-    /// Contains the ids of the nodes that await_immediate the signal
-    pub(crate) pending_await_immediate: Vec<usize>,
+    /// Contains the tasks of the nodes that await_immediate the signal
+    pub(crate) pending_await_immediate: Vec<Task>,
 
-    /// Contains the ids of the nodes that present the signal
-    pub(crate) pending_present: [Vec<(usize, usize)>; 2],
+    /// Contains the tasks of the nodes that present the signal
+    pub(crate) pending_present: [Vec<(Task, Task)>; 2],
 
     /// Contains the values of the signal
     pub(crate) values: SV,
@@ -85,7 +92,7 @@ where
     fn process_pending_present<'a>(&mut self, sub_runtime: &mut SubRuntime<'a>) {
         let nodes = take(&mut self.pending_present);
         for node in nodes {
-            sub_runtime.add_current(node.0);
+            sub_runtime.add_current_task(node.0);
         }
     }
 
@@ -100,14 +107,15 @@ where
         if self.last_set == sub_runtime.get_current_instant() {
             sub_runtime.add_current(node_true);
         } else {
-            self.pending_present.push((node_true, node_false));
+            let tasks = (sub_runtime.task(node_true), sub_runtime.task(node_false));
+            self.pending_present.push(tasks);
         }
     }
 
     fn on_end_of_instant(&mut self, sub_runtime: &mut SubRuntime) {
         let nodes = take(&mut self.pending_present);
         for node in nodes {
-            sub_runtime.add_current(node.1);
+            sub_runtime.add_current_task(node.1);
         }
     }
 }
@@ -135,7 +143,7 @@ where
     fn process_pending_present<'a>(&mut self, sub_runtime: &mut SubRuntime<'a>) {
         let nodes = take(&mut self.pending_present[sub_runtime.get_current_instant() % 2]);
         for node in nodes {
-            sub_runtime.add_current(node.0);
+            sub_runtime.add_current_task(node.0);
         }
     }
 
@@ -150,14 +158,15 @@ where
         if self.last_set == sub_runtime.get_current_instant() {
             sub_runtime.add_current(node_true);
         } else {
-            self.pending_present[sub_runtime.get_current_instant() % 2].push((node_true, node_false));
+            let tasks = (sub_runtime.task(node_true), sub_runtime.task(node_false));
+            self.pending_present[sub_runtime.get_current_instant() % 2].push(tasks);
         }
     }
 
     fn on_end_of_instant(&mut self, sub_runtime: &mut SubRuntime) {
         let nodes = take(&mut self.pending_present[(sub_runtime.get_current_instant() + 1) % 2]);
         for node in nodes {
-            sub_runtime.add_current(node.1);
+            sub_runtime.add_current_task(node.1);
         }
     }
 }
@@ -170,16 +179,16 @@ where
     /// Process pending await nodes on signal emission
     fn process_pending_await<'a>(&mut self, sub_runtime: &mut SubRuntime<'a>) {
         let nodes = take(&mut self.pending_await);
-        for node in nodes {
-            sub_runtime.add_next(node);
+        for task in nodes {
+            sub_runtime.add_next_task(task);
         }
     }
 
     /// Process pending await_immediate nodes on signal emission
     fn process_pending_await_immediate<'a>(&mut self, sub_runtime: &mut SubRuntime<'a>) {
         let nodes = take(&mut self.pending_await_immediate);
-        for node in nodes {
-            sub_runtime.add_current(node);
+        for task in nodes {
+            sub_runtime.add_current_task(task);
         }
     }
 
@@ -200,7 +209,7 @@ where
         if self.last_set == sub_runtime.get_current_instant() {
             sub_runtime.add_next(node);
         } else {
-            self.pending_await.push(node);
+            self.pending_await.push(sub_runtime.task(node));
         }
     }
 
@@ -209,7 +218,7 @@ where
         if self.last_set == sub_runtime.get_current_instant() {
             sub_runtime.add_current(node);
         } else {
-            self.pending_await_immediate.push(node);
+            self.pending_await_immediate.push(sub_runtime.task(node));
         }
     }
 
@@ -217,7 +226,11 @@ where
     /// This function should not be used in user mode, but Rust do not allow us to put
     /// this function in pub(crate), since it is part of a public trait
     fn pre_set(&self, current_instant: usize) -> bool {
-        self.pre_last_set + 1 == current_instant
+        if self.last_set == current_instant {
+            self.pre_last_set + 1 == current_instant
+        } else {
+            self.last_set + 1 == current_instant
+        }
     }
 
 
@@ -232,7 +245,7 @@ where
     fn emit(&mut self, emit_value: SV::E, sub_runtime: &mut SubRuntime) {
         // If the signal is already set, we are finished
         if self.last_set == sub_runtime.get_current_instant() {
-            self.values.gather(emit_value);
+            self.values.gather_from(sub_runtime.get_current_node(), emit_value);
             return;
         }
 
@@ -241,7 +254,7 @@ where
 
         self.update_values(sub_runtime.get_current_instant());
 
-        self.values.gather(emit_value);
+        self.values.gather_from(sub_runtime.get_current_node(), emit_value);
 
         // We process the awaiting nodes
         self.process_pending_await_immediate(sub_runtime);
@@ -286,6 +299,13 @@ pub trait SignalValue {
     /// Gather the emitted value
     fn gather(&mut self, emit_value: Self::E);
 
+    /// Gather the value emitted by the main node `node`
+    ///
+    /// Defaults to `gather`, the emitting node is only needed to order the emissions.
+    fn gather_from(&mut self, _node: usize, emit_value: Self::E) {
+        self.gather(emit_value)
+    }
+
     /// Reset the value stored by the signal,
     /// and stored the last one as the last instant value
     fn reset_value(&mut self);
@@ -439,6 +459,491 @@ where
 }
 
 
+//  ____   ____ ____  _                   ___     __    _
+// / ___| / ___/ ___|(_) __ _ _ __   __ _| \ \   / /_ _| |_   _  ___
+// \___ \| |   \___ \| |/ _` | '_ \ / _` | |\ \ / / _` | | | | |/ _ \
+//  ___) | |___ ___) | | (_| | | | | (_| | | \ V / (_| | | |_| |  __/
+// |____/ \____|____/|_|\__, |_| |_|\__,_|_|  \_/ \__,_|_|\__,_|\___|
+//                      |___/
+
+#[cfg(not(feature = "par"))]
+mod scvalue_content {
+    use super::*;
+
+    /// Structure representing the values of a single consumer signal
+    ///
+    /// The value of the last instant is moved to its reader instead of being cloned,
+    /// only the default value is cloned at each instant.
+    pub struct SCSignalValue<E, V> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant, `None` once it has been read
+        pub(crate) pre_value: RefCell<Option<V>>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V)>,
+    }
+
+    impl<E, V> SCSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new single consumer signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SCSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: RefCell::new(Some(default_value)),
+                gather,
+            }
+        }
+    }
+
+    impl<E, V> SignalRuntime<SCSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose value is moved to its only reader
+        pub(crate) fn new_sc(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntime::new(SCSignalValue::new(default_value, gather))
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod scvalue_content {
+    use super::*;
+
+    /// Structure representing the values of a single consumer signal
+    ///
+    /// The value of the last instant is moved to its reader instead of being cloned,
+    /// only the default value is cloned at each instant.
+    pub struct SCSignalValue<E: Send, V: Send> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant, `None` once it has been read
+        pub(crate) pre_value: RefCell<Option<V>>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V) + Send>,
+    }
+
+    impl<E: Send, V: Send> SCSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new single consumer signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SCSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: RefCell::new(Some(default_value)),
+                gather,
+            }
+        }
+    }
+
+    impl<E: Send, V: Send> SignalRuntime<SCSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose value is moved to its only reader
+        pub(crate) fn new_sc(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntime::new(SCSignalValue::new(default_value, gather))
+        }
+    }
+}
+
+pub use self::scvalue_content::*;
+
+
+impl<E: OptSend, V: OptSend> SignalValue for SCSignalValue<E, V>
+where
+    V: Clone,
+{
+    type E = E;
+    type V = V;
+
+    /// Moves the value of the last instant out, it panics if it was already read.
+    fn get_pre_value(&self) -> V {
+        match self.pre_value.borrow_mut().take() {
+            Some(v) => v,
+            None => panic!("single consumer signal read twice"),
+        }
+    }
+
+    fn gather(&mut self, emit_value: E) {
+        (&mut self.gather)(emit_value, &mut self.current_value)
+    }
+
+    fn reset_value(&mut self) {
+        let current = mem::replace(&mut self.current_value, self.default_value.clone());
+        self.pre_value = RefCell::new(Some(current));
+    }
+}
+
+
+//  ____  _                        _ ____  _                   ___     __    _
+// / ___|| |__   __ _ _ __ ___  __| / ___|(_) __ _ _ __   __ _| \ \   / /_ _| |_   _  ___
+// \___ \| '_ \ / _` | '__/ _ \/ _` \___ \| |/ _` | '_ \ / _` | |\ \ / / _` | | | | |/ _ \
+//  ___) | | | | (_| | | |  __/ (_| |___) | | (_| | | | | (_| | | \ V / (_| | | |_| |  __/
+// |____/|_| |_|\__,_|_|  \___|\__,_|____/|_|\__, |_| |_|\__,_|_|  \_/ \__,_|_|\__,_|\___|
+//                                           |___/
+
+#[cfg(not(feature = "par"))]
+mod sharedvalue_content {
+    use std::rc::Rc;
+    use super::*;
+
+    /// Shared pointer to the value of a `SharedSignalValue` at the last instant
+    pub type Snapshot<V> = Rc<V>;
+
+    /// Structure representing the values of a multi consumer signal whose readers share
+    /// the same snapshot of the value of the last instant, instead of cloning it.
+    pub struct SharedSignalValue<E, V> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant, frozen at the end of the instant
+        pub(crate) pre_value: Snapshot<V>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V)>,
+    }
+
+    impl<E, V> SharedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new shared signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SharedSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: Rc::new(default_value),
+                gather,
+            }
+        }
+    }
+
+    impl<E, V> SignalRuntime<SharedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose value is shared by its readers
+        pub(crate) fn new_shared(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntime::new(SharedSignalValue::new(default_value, gather))
+        }
+    }
+
+    impl<E, V> SignalValue for SharedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        type E = E;
+        type V = Snapshot<V>;
+
+        fn get_pre_value(&self) -> Snapshot<V> {
+            self.pre_value.clone()
+        }
+
+        fn gather(&mut self, emit_value: E) {
+            (&mut self.gather)(emit_value, &mut self.current_value)
+        }
+
+        fn reset_value(&mut self) {
+            let current = mem::replace(&mut self.current_value, self.default_value.clone());
+            self.pre_value = Rc::new(current);
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod sharedvalue_content {
+    use std::sync::Arc;
+    use super::*;
+
+    /// Shared pointer to the value of a `SharedSignalValue` at the last instant
+    pub type Snapshot<V> = Arc<V>;
+
+    /// Structure representing the values of a multi consumer signal whose readers share
+    /// the same snapshot of the value of the last instant, instead of cloning it.
+    pub struct SharedSignalValue<E: Send, V: Send + Sync> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant, frozen at the end of the instant
+        pub(crate) pre_value: Snapshot<V>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V) + Send>,
+    }
+
+    impl<E: Send, V: Send + Sync> SharedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new shared signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SharedSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: Arc::new(default_value),
+                gather,
+            }
+        }
+    }
+
+    impl<E: Send, V: Send + Sync> SignalRuntime<SharedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose value is shared by its readers
+        pub(crate) fn new_shared(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntime::new(SharedSignalValue::new(default_value, gather))
+        }
+    }
+
+    impl<E: Send, V: Send + Sync> SignalValue for SharedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        type E = E;
+        type V = Snapshot<V>;
+
+        fn get_pre_value(&self) -> Snapshot<V> {
+            self.pre_value.clone()
+        }
+
+        fn gather(&mut self, emit_value: E) {
+            (&mut self.gather)(emit_value, &mut self.current_value)
+        }
+
+        fn reset_value(&mut self) {
+            let current = mem::replace(&mut self.current_value, self.default_value.clone());
+            self.pre_value = Arc::new(current);
+        }
+    }
+}
+
+pub use self::sharedvalue_content::*;
+
+
+//   ___          _                   _ ____  _                   ___     __    _
+//  / _ \ _ __ __| | ___ _ __ ___  __| / ___|(_) __ _ _ __   __ _| \ \   / /_ _| |_   _  ___
+// | | | | '__/ _` |/ _ \ '__/ _ \/ _` \___ \| |/ _` | '_ \ / _` | |\ \ / / _` | | | | |/ _ \
+// | |_| | | | (_| |  __/ | |  __/ (_| |___) | | (_| | | | | (_| | | \ V / (_| | | |_| |  __/
+//  \___/|_|  \__,_|\___|_|  \___|\__,_|____/|_|\__, |_| |_|\__,_|_|  \_/ \__,_|_|\__,_|\___|
+//                                              |___/
+
+#[cfg(not(feature = "par"))]
+mod orderedvalue_content {
+    use super::*;
+
+    /// Structure representing the values of a multi consumer signal whose emissions are
+    /// gathered by increasing id of the emitting node, at the end of the instant.
+    ///
+    /// The value of the signal does not depend on the scheduling of the nodes of the
+    /// graph compiled before the runtime starts. It still does in two cases:
+    /// the ids of the nodes compiled at runtime (see `process::flatten`) depend on the order
+    /// in which they were compiled, and the emissions of a node run several times in the
+    /// same instant are gathered in the order they arrived.
+    pub struct OrderedSignalValue<E, V> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant
+        pub(crate) pre_value: V,
+
+        /// The values emitted during the current instant, with the id of their emitting node
+        pub(crate) emitted: Vec<(usize, E)>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V)>,
+    }
+
+    impl<E, V> OrderedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new ordered signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            OrderedSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: default_value,
+                emitted: vec![],
+                gather,
+            }
+        }
+    }
+
+    impl<E, V> SignalRuntime<OrderedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose emissions are gathered in a deterministic order
+        pub(crate) fn new_ordered(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntime::new(OrderedSignalValue::new(default_value, gather))
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod orderedvalue_content {
+    use super::*;
+
+    /// Structure representing the values of a multi consumer signal whose emissions are
+    /// gathered by increasing id of the emitting node, at the end of the instant.
+    ///
+    /// The value of the signal does not depend on the scheduling of the nodes of the
+    /// graph compiled before the runtime starts. It still does in two cases:
+    /// the ids of the nodes compiled at runtime (see `process::flatten`) depend on the order
+    /// in which they were compiled, and the emissions of a node run several times in the
+    /// same instant are gathered in the order they arrived.
+    pub struct OrderedSignalValue<E: Send, V: Send> {
+        /// The default value of the signal
+        pub(crate) default_value: V,
+
+        /// The value of the signal for the current instant
+        pub(crate) current_value: V,
+
+        /// The value of the signal at the last instant
+        pub(crate) pre_value: V,
+
+        /// The values emitted during the current instant, with the id of their emitting node
+        pub(crate) emitted: Vec<(usize, E)>,
+
+        /// The function used to gather the signals
+        pub(crate) gather: Box<FnMut(E, &mut V) + Send>,
+    }
+
+    impl<E: Send, V: Send> OrderedSignalValue<E, V>
+    where
+        V: Clone,
+    {
+        /// Creates a new ordered signal, given a default value and a gather function
+        fn new(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            OrderedSignalValue {
+                default_value: default_value.clone(),
+                current_value: default_value.clone(),
+                pre_value: default_value,
+                emitted: vec![],
+                gather,
+            }
+        }
+    }
+
+    impl<E: Send, V: Send> SignalRuntime<OrderedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a new signal runtime, whose emissions are gathered in a deterministic order
+        pub(crate) fn new_ordered(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntime::new(OrderedSignalValue::new(default_value, gather))
+        }
+    }
+}
+
+pub use self::orderedvalue_content::*;
+
+
+impl<E: OptSend, V: OptSend> SignalValue for OrderedSignalValue<E, V>
+where
+    V: Clone,
+{
+    type E = E;
+    type V = V;
+    fn get_pre_value(&self) -> V {
+        self.pre_value.clone()
+    }
+
+    fn gather(&mut self, emit_value: E) {
+        self.emitted.push((usize::max_value(), emit_value));
+    }
+
+    fn gather_from(&mut self, node: usize, emit_value: E) {
+        self.emitted.push((node, emit_value));
+    }
+
+    fn reset_value(&mut self) {
+        // The sort is stable, so the emissions of a node keep their order
+        let mut emitted = take(&mut self.emitted);
+        emitted.sort_by_key(|&(node, _)| node);
+        for (_, emit_value) in emitted {
+            (&mut self.gather)(emit_value, &mut self.current_value);
+        }
+        mem::swap(&mut self.pre_value, &mut self.current_value);
+        self.current_value = self.default_value.clone();
+    }
+}
+
+
+//  ____                            ____  _                   ___     __    _
+// / ___| _ __   __ ___      ___ __/ ___|(_) __ _ _ __   __ _| \ \   / /_ _| |_   _  ___
+// \___ \| '_ \ / _` \ \ /\ / / '_ \___ \| |/ _` | '_ \ / _` | |\ \ / / _` | | | | |/ _ \
+//  ___) | |_) | (_| |\ V  V /| | | |__) | | (_| | | | | (_| | | \ V / (_| | | |_| |  __/
+// |____/| .__/ \__,_| \_/\_/ |_| |_|____/|_|\__, |_| |_|\__,_|_|  \_/ \__,_|_|\__,_|\___|
+//       |_|                                |___/
+
+/// Structure representing the values of a signal whose emitted values are processes.
+///
+/// All the processes emitted during an instant are given at the next instant to the first
+/// reader only, since processes cannot be cloned. See `process::spawner`.
+/// The processes that are not read are kept for the next reader, even after several instants.
+pub struct SpawnSignalValue<'a> {
+    /// The processes emitted during the current instant
+    current: Vec<BoxedSpawn<'a>>,
+
+    /// The processes emitted before the current instant and not yet read
+    pre: RefCell<Vec<BoxedSpawn<'a>>>,
+}
+
+impl<'a> SpawnSignalValue<'a> {
+    /// Creates a new spawn signal value, without any process
+    pub fn new() -> Self {
+        SpawnSignalValue {
+            current: vec![],
+            pre: RefCell::new(vec![]),
+        }
+    }
+}
+
+impl<'a> SignalValue for SpawnSignalValue<'a> {
+    type E = BoxedSpawn<'a>;
+    type V = Vec<BoxedSpawn<'a>>;
+
+    fn get_pre_value(&self) -> Self::V {
+        take(&mut *self.pre.borrow_mut())
+    }
+
+    fn gather(&mut self, emit_value: BoxedSpawn<'a>) {
+        self.current.push(emit_value);
+    }
+
+    fn reset_value(&mut self) {
+        self.pre.borrow_mut().append(&mut self.current);
+    }
+}
+
+
 //  ____  _                   _
 // / ___|(_) __ _ _ __   __ _| |
 // \___ \| |/ _` | '_ \ / _` | |
@@ -512,6 +1017,44 @@ mod content {
         }
     }
 
+    impl<E, V> SignalRuntimeRef<SCSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new single consumer signal runtime
+        pub fn new_sc(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Rc::new(RefCell::new(SignalRuntime::new_sc(default_value, gather))),
+            }
+        }
+    }
+
+    impl<E, V> SignalRuntimeRef<SharedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new signal runtime whose readers share its value
+        pub fn new_shared(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Rc::new(RefCell::new(SignalRuntime::new_shared(default_value, gather))),
+            }
+        }
+    }
+
+    impl<E, V> SignalRuntimeRef<OrderedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new signal runtime gathering its emissions
+        /// by increasing id of the emitting node, see `OrderedSignalValue` for the cases
+        /// where this order still depends on the scheduling
+        pub fn new_ordered(default_value: V, gather: Box<FnMut(E, &mut V)>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Rc::new(RefCell::new(SignalRuntime::new_ordered(default_value, gather))),
+            }
+        }
+    }
+
     impl<SV> SignalRuntimeRef<SV>
     where
         SV: SignalValue,
@@ -643,6 +1186,44 @@ mod content {
         }
     }
 
+    impl<E: Send, V: Send> SignalRuntimeRef<SCSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new single consumer signal runtime
+        pub fn new_sc(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Arc::new(Mutex::new(SignalRuntime::new_sc(default_value, gather))),
+            }
+        }
+    }
+
+    impl<E: Send, V: Send + Sync> SignalRuntimeRef<SharedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new signal runtime whose readers share its value
+        pub fn new_shared(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Arc::new(Mutex::new(SignalRuntime::new_shared(default_value, gather))),
+            }
+        }
+    }
+
+    impl<E: Send, V: Send> SignalRuntimeRef<OrderedSignalValue<E, V>>
+    where
+        V: Clone,
+    {
+        /// Create a shared pointer to a new signal runtime gathering its emissions
+        /// by increasing id of the emitting node, see `OrderedSignalValue` for the cases
+        /// where this order still depends on the scheduling
+        pub fn new_ordered(default_value: V, gather: Box<FnMut(E, &mut V) + Send>) -> Self {
+            SignalRuntimeRef {
+                signal_runtime: Arc::new(Mutex::new(SignalRuntime::new_ordered(default_value, gather))),
+            }
+        }
+    }
+
     impl<SV> SignalRuntimeRef<SV>
     where
         SV: SignalValue,
@@ -742,3 +1323,426 @@ mod content {
 }
 
 pub use self::content::*;
+
+//  ____  _                   _ _____
+// / ___|(_) __ _ _ __   __ _| | ____|_  ___ __  _ __
+// \___ \| |/ _` | '_ \ / _` | |  _| \ \/ / '_ \| '__|
+//  ___) | | (_| | | | | (_| | | |___ >  <| |_) | |
+// |____/|_|\__, |_| |_|\__,_|_|_____/_/\_\ .__/|_|
+//          |___/                         |_|
+
+/// A boolean expression over signals, tested by `process::present_expr` and awaited
+/// by `process::await_expr`.
+///
+/// Build leaves with `sig_expr`, and combine them with `and`, `or` and `!`.
+pub enum SignalExpr<'a> {
+    Sig(Box<PureSignal<'a> + 'a>),
+    Not(Box<SignalExpr<'a>>),
+    And(Box<SignalExpr<'a>>, Box<SignalExpr<'a>>),
+    Or(Box<SignalExpr<'a>>, Box<SignalExpr<'a>>),
+}
+
+/// Builds a `SignalExpr` that is true when `signal` is present.
+pub fn sig_expr<'a, S: PureSignal<'a>>(signal: S) -> SignalExpr<'a> {
+    SignalExpr::Sig(box signal)
+}
+
+impl<'a> SignalExpr<'a> {
+    /// Conjunction of two expressions
+    pub fn and(self, e: SignalExpr<'a>) -> Self {
+        SignalExpr::And(box self, box e)
+    }
+
+    /// Disjunction of two expressions
+    pub fn or(self, e: SignalExpr<'a>) -> Self {
+        SignalExpr::Or(box self, box e)
+    }
+
+    /// Expression true when any of the signals is present.
+    /// Panics if there is no signal.
+    pub fn any<S: PureSignal<'a>, I: IntoIterator<Item = S>>(signals: I) -> Self {
+        let mut it = signals.into_iter().map(sig_expr);
+        let first = it.next().expect("SignalExpr::any needs at least one signal");
+        it.fold(first, SignalExpr::or)
+    }
+
+    /// Expression true when all the signals are present.
+    /// Panics if there is no signal.
+    pub fn all<S: PureSignal<'a>, I: IntoIterator<Item = S>>(signals: I) -> Self {
+        let mut it = signals.into_iter().map(sig_expr);
+        let first = it.next().expect("SignalExpr::all needs at least one signal");
+        it.fold(first, SignalExpr::and)
+    }
+
+    /// Returns the signals of the expression, always in the same order.
+    pub(crate) fn leaves(&self) -> Vec<&PureSignal<'a>> {
+        let mut res = vec![];
+        self.push_leaves(&mut res);
+        res
+    }
+
+    fn push_leaves<'b>(&'b self, res: &mut Vec<&'b PureSignal<'a>>) {
+        match *self {
+            SignalExpr::Sig(ref s) => res.push(&**s),
+            SignalExpr::Not(ref e) => e.push_leaves(res),
+            SignalExpr::And(ref e1, ref e2) | SignalExpr::Or(ref e1, ref e2) => {
+                e1.push_leaves(res);
+                e2.push_leaves(res);
+            }
+        }
+    }
+
+    /// Evaluates the expression in a three-valued logic, where `None` means unknown.
+    ///
+    /// `f` gives the value of each signal.
+    pub(crate) fn eval<F>(&self, f: &F) -> Option<bool>
+    where
+        F: Fn(&PureSignal<'a>) -> Option<bool>,
+    {
+        match *self {
+            SignalExpr::Sig(ref s) => f(&**s),
+            SignalExpr::Not(ref e) => e.eval(f).map(|b| !b),
+            SignalExpr::And(ref e1, ref e2) => {
+                match (e1.eval(f), e2.eval(f)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            SignalExpr::Or(ref e1, ref e2) => {
+                match (e1.eval(f), e2.eval(f)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+impl<'a> ::std::ops::Not for SignalExpr<'a> {
+    type Output = SignalExpr<'a>;
+    fn not(self) -> Self {
+        SignalExpr::Not(box self)
+    }
+}
+
+impl<'a> Clone for SignalExpr<'a> {
+    fn clone(&self) -> Self {
+        match *self {
+            SignalExpr::Sig(ref s) => SignalExpr::Sig(s.clone2()),
+            SignalExpr::Not(ref e) => SignalExpr::Not(box (**e).clone()),
+            SignalExpr::And(ref e1, ref e2) => SignalExpr::And(box (**e1).clone(), box (**e2).clone()),
+            SignalExpr::Or(ref e1, ref e2) => SignalExpr::Or(box (**e1).clone(), box (**e2).clone()),
+        }
+    }
+}
+
+
+impl<'a> SignalRuntimeRef<SpawnSignalValue<'a>> {
+    /// Create a shared pointer to a new signal runtime whose values are processes
+    pub fn new_spawn() -> Self {
+        SignalRuntimeRef::new(SpawnSignalValue::new())
+    }
+}
+
+
+//  _                    _ ____  _                   _
+// | |    ___   ___ __ _| / ___|(_) __ _ _ __   __ _| |
+// | |   / _ \ / __/ _` | \___ \| |/ _` | '_ \ / _` | |
+// | |__| (_) | (_| (_| | |___) | | (_| | | | | (_| | |
+// |_____\___/ \___\__,_|_|____/|_|\__, |_| |_|\__,_|_|
+//                                 |___/
+
+/// A handle on a locally scoped signal (see `process::local_signal`).
+///
+/// The handle is shared by all the processes of the scope, but it points to a fresh signal
+/// each time the scope is entered, and to no signal out of the scope.
+/// Using it out of its scope panics.
+#[cfg(not(feature = "par"))]
+mod local_content {
+    use std::rc::{Rc, Weak};
+    use std::cell::RefCell;
+    use graph::KillHook;
+    use OptSend;
+
+    pub struct LocalSignal<S> {
+        pub(crate) signal: Rc<RefCell<Option<S>>>,
+    }
+
+    impl<S: Clone> LocalSignal<S> {
+        /// Creates a handle pointing to no signal.
+        pub fn new() -> Self {
+            LocalSignal { signal: Rc::new(RefCell::new(None)) }
+        }
+
+        /// Returns the signal currently in the scope.
+        pub fn get(&self) -> S {
+            match *self.signal.borrow() {
+                Some(ref s) => s.clone(),
+                None => panic!("local signal used out of its scope"),
+            }
+        }
+
+        pub(crate) fn set(&self, signal: Option<S>) {
+            *self.signal.borrow_mut() = signal;
+        }
+
+        /// Returns a hook dropping the signal from the handle when the scope is killed.
+        pub(crate) fn clear_hook(&self) -> Box<KillHook>
+        where
+            S: OptSend + 'static,
+        {
+            box LocalSignalClear(Rc::downgrade(&self.signal))
+        }
+    }
+
+    /// Cleanup dropping the signal of a local signal handle, see `LocalSignal::clear_hook`.
+    struct LocalSignalClear<S>(Weak<RefCell<Option<S>>>);
+
+    impl<S: OptSend> KillHook for LocalSignalClear<S> {
+        fn killed(&self) {
+            if let Some(signal) = self.0.upgrade() {
+                *signal.borrow_mut() = None;
+            }
+        }
+        fn dropped(&self) -> bool {
+            self.0.upgrade().is_none()
+        }
+    }
+
+    impl<S> Clone for LocalSignal<S> {
+        fn clone(&self) -> Self {
+            LocalSignal { signal: self.signal.clone() }
+        }
+    }
+}
+
+#[cfg(feature = "par")]
+mod local_content {
+    use std::sync::{Arc, Mutex, Weak};
+    use graph::KillHook;
+    use OptSend;
+
+    pub struct LocalSignal<S> {
+        pub(crate) signal: Arc<Mutex<Option<S>>>,
+    }
+
+    impl<S: Clone> LocalSignal<S> {
+        /// Creates a handle pointing to no signal.
+        pub fn new() -> Self {
+            LocalSignal { signal: Arc::new(Mutex::new(None)) }
+        }
+
+        /// Returns the signal currently in the scope.
+        pub fn get(&self) -> S {
+            match *self.signal.lock().unwrap() {
+                Some(ref s) => s.clone(),
+                None => panic!("local signal used out of its scope"),
+            }
+        }
+
+        pub(crate) fn set(&self, signal: Option<S>) {
+            *self.signal.lock().unwrap() = signal;
+        }
+
+        /// Returns a hook dropping the signal from the handle when the scope is killed.
+        pub(crate) fn clear_hook(&self) -> Box<KillHook>
+        where
+            S: OptSend + 'static,
+        {
+            box LocalSignalClear(Arc::downgrade(&self.signal))
+        }
+    }
+
+    /// Cleanup dropping the signal of a local signal handle, see `LocalSignal::clear_hook`.
+    struct LocalSignalClear<S>(Weak<Mutex<Option<S>>>);
+
+    impl<S: OptSend> KillHook for LocalSignalClear<S> {
+        fn killed(&self) {
+            if let Some(signal) = self.0.upgrade() {
+                *signal.lock().unwrap() = None;
+            }
+        }
+        fn dropped(&self) -> bool {
+            self.0.upgrade().is_none()
+        }
+    }
+
+    impl<S> Clone for LocalSignal<S> {
+        fn clone(&self) -> Self {
+            LocalSignal { signal: self.signal.clone() }
+        }
+    }
+}
+
+pub use self::local_content::*;
+
+impl<'a, S> PureSignal<'a> for LocalSignal<S>
+where
+    S: PureSignal<'a> + Clone,
+{
+    fn await(&self, sub_runtime: &mut SubRuntime<'a>, node: usize) {
+        self.get().await(sub_runtime, node)
+    }
+
+    fn await_immediate(&self, sub_runtime: &mut SubRuntime<'a>, node: usize) {
+        self.get().await_immediate(sub_runtime, node)
+    }
+
+    fn present(&self, sub_runtime: &mut SubRuntime<'a>, node_true: usize, node_false: usize) {
+        self.get().present(sub_runtime, node_true, node_false)
+    }
+
+    fn pre_set(&self, current_instant: usize) -> bool {
+        self.get().pre_set(current_instant)
+    }
+
+    fn is_set(&self, current_instant: usize) -> bool {
+        self.get().is_set(current_instant)
+    }
+
+    fn clone2(&self) -> Box<PureSignal<'a> + 'a> {
+        box self.clone()
+    }
+}
+
+impl<'a, S> Signal<'a> for LocalSignal<S>
+where
+    S: Signal<'a> + Clone,
+{
+    type E = S::E;
+    type V = S::V;
+
+    fn emit(&self, emit_value: Self::E, sub_runtime: &mut SubRuntime<'a>) {
+        self.get().emit(emit_value, sub_runtime)
+    }
+
+    fn get_pre_value(&self, current_instant: usize) -> Self::V {
+        self.get().get_pre_value(current_instant)
+    }
+}
+
+
+//   ____       _   _
+//  / ___| __ _| |_| |__   ___ _ __
+// | |  _ / _` | __| '_ \ / _ \ '__|
+// | |_| | (_| | |_| | | |  __/ |
+//  \____|\__,_|\__|_| |_|\___|_|
+
+// Multi consumer signals with standard gather functions.
+
+impl<E: OptSend + 'static> SignalRuntimeRef<MCSignalValue<E, Vec<E>>>
+where
+    E: Clone,
+{
+    /// Create a signal whose value is the vector of the values emitted during the instant,
+    /// in the order they were gathered.
+    ///
+    /// In `par` mode, this order is unspecified and may change from one run to another,
+    /// use `new_ordered` to get a deterministic value.
+    pub fn new_collect() -> Self {
+        SignalRuntimeRef::new_mc(vec![], box |e: E, v: &mut Vec<E>| v.push(e))
+    }
+}
+
+impl<E: OptSend + 'static> SignalRuntimeRef<MCSignalValue<E, E>>
+where
+    E: Clone,
+{
+    /// Create a signal whose value is the sum of the values emitted during the instant.
+    pub fn new_sum() -> Self
+    where
+        E: AddAssign + Sum,
+    {
+        SignalRuntimeRef::new_mc(iter::empty().sum(), box |e: E, v: &mut E| *v += e)
+    }
+
+    /// Create a signal whose value is the product of the values emitted during the instant.
+    pub fn new_product() -> Self
+    where
+        E: MulAssign + Product,
+    {
+        SignalRuntimeRef::new_mc(iter::empty().product(), box |e: E, v: &mut E| *v *= e)
+    }
+}
+
+impl<E: OptSend + 'static> SignalRuntimeRef<MCSignalValue<E, Option<E>>>
+where
+    E: Clone,
+{
+    /// Create a signal whose value is the minimum of the values emitted during the instant,
+    /// or `None` if the signal was not emitted.
+    pub fn new_min() -> Self
+    where
+        E: Ord,
+    {
+        SignalRuntimeRef::new_mc(None, box |e: E, v: &mut Option<E>| {
+            *v = Some(match v.take() {
+                Some(old) => cmp::min(old, e),
+                None => e,
+            })
+        })
+    }
+
+    /// Create a signal whose value is the maximum of the values emitted during the instant,
+    /// or `None` if the signal was not emitted.
+    pub fn new_max() -> Self
+    where
+        E: Ord,
+    {
+        SignalRuntimeRef::new_mc(None, box |e: E, v: &mut Option<E>| {
+            *v = Some(match v.take() {
+                Some(old) => cmp::max(old, e),
+                None => e,
+            })
+        })
+    }
+
+    /// Create a signal whose value is the first value emitted during the instant,
+    /// or `None` if the signal was not emitted.
+    ///
+    /// In `par` mode, the emissions are gathered in an unspecified order, so the first one
+    /// may change from one run to another, use `new_ordered` to get a deterministic value.
+    pub fn new_first() -> Self {
+        SignalRuntimeRef::new_mc(None, box |e: E, v: &mut Option<E>| {
+            if v.is_none() {
+                *v = Some(e);
+            }
+        })
+    }
+
+    /// Create a signal whose value is the last value emitted during the instant,
+    /// or `None` if the signal was not emitted.
+    ///
+    /// In `par` mode, the emissions are gathered in an unspecified order, so the last one
+    /// may change from one run to another, use `new_ordered` to get a deterministic value.
+    pub fn new_last() -> Self {
+        SignalRuntimeRef::new_mc(None, box |e: E, v: &mut Option<E>| *v = Some(e))
+    }
+}
+
+impl<E: OptSend + 'static> SignalRuntimeRef<MCSignalValue<E, HashSet<E>>>
+where
+    E: Clone + Eq + Hash,
+{
+    /// Create a signal whose value is the set of the values emitted during the instant.
+    pub fn new_union() -> Self {
+        SignalRuntimeRef::new_mc(HashSet::new(), box |e: E, v: &mut HashSet<E>| {
+            v.insert(e);
+        })
+    }
+}
+
+impl<E: OptSend + 'static> SignalRuntimeRef<MCSignalValue<E, HashMap<E, usize>>>
+where
+    E: Clone + Eq + Hash,
+{
+    /// Create a signal whose value maps each value emitted during the instant to its number
+    /// of emissions.
+    pub fn new_count() -> Self {
+        SignalRuntimeRef::new_mc(HashMap::new(), box |e: E, v: &mut HashMap<E, usize>| {
+            *v.entry(e).or_insert(0) += 1;
+        })
+    }
+}